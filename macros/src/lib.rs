@@ -0,0 +1,161 @@
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input, Data, DeriveInput, Error, Fields, Ident, ItemFn, LitStr, Token, Type,
+};
+
+/// Builds a `NegotiatorLayer` over the given media types, checked at compile time, next to the
+/// handler as `<fn>_negotiator()`.
+///
+/// With `route = <method>("<path>")`, `<fn>_route(router)` also registers the handler with its
+/// negotiator on an `axum::Router<S>`, where `S` is `()` unless given with `state = <type>`.
+#[proc_macro_attribute]
+pub fn produces(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as ProducesArgs);
+    let handler = parse_macro_input!(item as ItemFn);
+    if args.types.is_empty() {
+        return Error::new_spanned(
+            &handler.sig.ident,
+            "#[produces] requires at least one media type",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let vis = &handler.vis;
+    let ident = &handler.sig.ident;
+    let negotiator = format_ident!("{}_negotiator", ident);
+    let types = args.types.iter();
+    let checks = args.types.iter().map(|ty| {
+        let msg = format!("invalid produced media type {:?}", ty.value());
+        quote! {
+            const _: () = ::std::assert!(
+                ::http_negotiator::is_valid_supported(
+                    ::http_negotiator::NegotiationKind::ContentType,
+                    #ty,
+                ),
+                #msg,
+            );
+        }
+    });
+    let route = args.route.map(|(method, path)| {
+        let route = format_ident!("{}_route", ident);
+        let state = args
+            .state
+            .map_or_else(|| quote!(()), |state| quote!(#state));
+        quote! {
+            #vis fn #route(router: ::axum::Router<#state>) -> ::axum::Router<#state> {
+                router.route(#path, ::axum::routing::#method(#ident).layer(#negotiator()))
+            }
+        }
+    });
+    quote! {
+        #handler
+
+        #vis fn #negotiator() -> ::http_negotiator::NegotiatorLayer<
+            ::http_negotiator::ContentTypeNegotiation,
+            &'static str,
+        > {
+            #(#checks)*
+            ::http_negotiator::NegotiatorLayer::new(
+                ::http_negotiator::Negotiator::new([#(#types),*])
+                    .expect("media types are checked at compile time"),
+            )
+        }
+
+        #route
+    }
+    .into()
+}
+
+struct ProducesArgs {
+    types: Vec<LitStr>,
+    route: Option<(Ident, LitStr)>,
+    state: Option<Type>,
+}
+
+impl Parse for ProducesArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = Self {
+            types: Vec::new(),
+            route: None,
+            state: None,
+        };
+        while !input.is_empty() {
+            if input.peek(LitStr) {
+                args.types.push(input.parse()?);
+            } else {
+                let key = input.parse::<Ident>()?;
+                input.parse::<Token![=]>()?;
+                match key.to_string().as_str() {
+                    "route" if args.route.is_none() => {
+                        let method = input.parse()?;
+                        let path;
+                        parenthesized!(path in input);
+                        args.route = Some((method, path.parse()?));
+                    }
+                    "state" if args.state.is_none() => args.state = Some(input.parse()?),
+                    _ => return Err(Error::new(key.span(), "unexpected #[produces] argument")),
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+#[proc_macro_derive(AsNegotiationStr, attributes(negotiation))]
+pub fn derive_as_negotiation_str(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    let Data::Enum(data) = &input.data else {
+        return Error::new_spanned(
+            &input.ident,
+            "AsNegotiationStr can only be derived for enums",
+        )
+        .to_compile_error()
+        .into();
+    };
+
+    let mut arms = Vec::with_capacity(data.variants.len());
+    for variant in &data.variants {
+        let Some(attr) = variant
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("negotiation"))
+        else {
+            return Error::new_spanned(
+                &variant.ident,
+                "missing #[negotiation(\"...\")] attribute on variant",
+            )
+            .to_compile_error()
+            .into();
+        };
+        let value = match attr.parse_args::<LitStr>() {
+            Ok(value) => value,
+            Err(err) => return err.to_compile_error().into(),
+        };
+        let ident = &variant.ident;
+        let pattern = match variant.fields {
+            Fields::Named(_) => quote!(Self::#ident { .. }),
+            Fields::Unnamed(_) => quote!(Self::#ident(..)),
+            Fields::Unit => quote!(Self::#ident),
+        };
+        arms.push(quote!(#pattern => #value,));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::http_negotiator::AsNegotiationStr for #ident #ty_generics #where_clause {
+            fn as_str(&self) -> &str {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+    .into()
+}
@@ -0,0 +1,136 @@
+use std::{fmt::Debug, time::SystemTime};
+
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub struct Availability {
+    pub from: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+}
+
+impl Availability {
+    pub fn always() -> Self {
+        Self::default()
+    }
+
+    pub fn from(from: SystemTime) -> Self {
+        Self {
+            from: Some(from),
+            until: None,
+        }
+    }
+
+    pub fn until(until: SystemTime) -> Self {
+        Self {
+            from: None,
+            until: Some(until),
+        }
+    }
+
+    pub fn between(from: SystemTime, until: SystemTime) -> Self {
+        Self {
+            from: Some(from),
+            until: Some(until),
+        }
+    }
+
+    pub fn is_always(&self) -> bool {
+        self.from.is_none() && self.until.is_none()
+    }
+
+    pub fn contains(&self, now: SystemTime) -> bool {
+        self.from.is_none_or(|from| from <= now) && self.until.is_none_or(|until| now < until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{Availability, Clock};
+    use crate::{ContentTypeNegotiation, Error, Negotiator};
+
+    #[derive(Debug)]
+    struct FixedClock(SystemTime);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> SystemTime {
+            self.0
+        }
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn contains() {
+        assert!(Availability::always().contains(at(0)));
+        assert!(!Availability::from(at(10)).contains(at(9)));
+        assert!(Availability::from(at(10)).contains(at(10)));
+        assert!(Availability::until(at(10)).contains(at(9)));
+        assert!(!Availability::until(at(10)).contains(at(10)));
+        assert!(Availability::between(at(10), at(20)).contains(at(15)));
+        assert!(!Availability::between(at(10), at(20)).contains(at(25)));
+    }
+
+    #[test]
+    fn negotiate() {
+        let negotiator = |now| {
+            Negotiator::<ContentTypeNegotiation, _>::new([
+                "application/vnd.api.v3+json",
+                "application/vnd.api.v2+json",
+            ])
+            .unwrap()
+            .with_availability("application/vnd.api.v3+json", Availability::from(at(100)))
+            .with_availability("application/vnd.api.v2+json", Availability::until(at(200)))
+            .with_clock(FixedClock(at(now)))
+        };
+        let header = "application/vnd.api.v3+json, application/vnd.api.v2+json";
+
+        assert_eq!(
+            negotiator(50).negotiate(header).unwrap(),
+            Some(&"application/vnd.api.v2+json")
+        );
+        assert_eq!(
+            negotiator(150).negotiate(header).unwrap(),
+            Some(&"application/vnd.api.v3+json")
+        );
+        assert_eq!(
+            negotiator(250)
+                .negotiate("application/vnd.api.v2+json")
+                .unwrap(),
+            None
+        );
+
+        // The first supported value is only a default while it is available.
+        assert_eq!(
+            negotiator(50).negotiate_opt(None).unwrap(),
+            Some(&"application/vnd.api.v2+json")
+        );
+        assert_eq!(
+            negotiator(50).negotiate_or_first(Some("image/png")),
+            Ok(&"application/vnd.api.v2+json")
+        );
+        let negotiator = negotiator(250).with_availability(
+            "application/vnd.api.v3+json",
+            Availability::between(at(100), at(200)),
+        );
+        assert_eq!(negotiator.negotiate_opt(None).unwrap(), None);
+        assert_eq!(
+            negotiator.negotiate_or_first(None),
+            Err(Error::NotAcceptable)
+        );
+    }
+}
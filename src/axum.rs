@@ -1,13 +1,27 @@
-use std::{marker::PhantomData, ops::Deref, sync::Arc};
+use std::{
+    marker::PhantomData,
+    ops::Deref,
+    sync::Arc,
+    task::{Context, Poll},
+};
 
 use async_trait::async_trait;
 use axum_core::{
     extract::{FromRef, FromRequestParts},
     response::{IntoResponse, Response},
 };
-use http::{request::Parts, StatusCode};
+use http::{
+    header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE},
+    request::Parts,
+    HeaderMap, Request, StatusCode,
+};
+use tower_layer::Layer;
+use tower_service::Service;
 
-use crate::{AsNegotiationStr, Error, NegotiationType, Negotiator};
+use crate::{
+    combined_header, content_language, AsNegotiationStr, Error, LanguageNegotiation,
+    NegotiationType, Negotiator,
+};
 
 #[derive(Clone, Debug)]
 pub struct Negotiation<N, T>(pub PhantomData<N>, pub T);
@@ -38,52 +52,245 @@ where
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let negotiator = Arc::<Negotiator<N, T>>::from_ref(state);
-        let header = parts.headers.get(N::associated_header());
-        let res = match header {
-            Some(header) => {
-                let header = header
-                    .to_str()
-                    .map_err(|_| NegotiationError::InvalidAcceptHeader)?;
-                negotiator
-                    .negotiate(header)
-                    .map_err(NegotiationError::NegotiationFailure)?
-            }
-            None => None,
-        };
-
         Ok(Negotiation(
             PhantomData,
-            res.unwrap_or_else(|| negotiator.unwrap_first()).clone(),
+            negotiate_parts(&negotiator, parts)?,
         ))
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct RouteNegotiation<N, T>(pub PhantomData<N>, pub T);
+
+impl<N, T> RouteNegotiation<N, T> {
+    pub fn into_inner(self) -> T {
+        self.1
+    }
+}
+
+impl<N, T> Deref for RouteNegotiation<N, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.1
+    }
+}
+
+#[async_trait]
+impl<S, N, T> FromRequestParts<S> for RouteNegotiation<N, T>
+where
+    S: Send + Sync,
+    N: NegotiationType + Send + Sync + 'static,
+    N::Parsed: Send + Sync,
+    T: AsNegotiationStr + Clone + Send + Sync + 'static,
+{
+    type Rejection = NegotiationError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let negotiator = parts
+            .extensions
+            .get::<Arc<Negotiator<N, T>>>()
+            .cloned()
+            .ok_or(NegotiationError::MissingNegotiator)?;
+        Ok(RouteNegotiation(
+            PhantomData,
+            negotiate_parts(&negotiator, parts)?,
+        ))
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(kind = ?N::KIND))
+)]
+fn negotiate_parts<N, T>(
+    negotiator: &Negotiator<N, T>,
+    parts: &Parts,
+) -> Result<T, NegotiationError>
+where
+    N: NegotiationType,
+    T: AsNegotiationStr + Clone,
+{
+    let header = combined_header(&parts.headers, negotiator.header_name());
+    let res = negotiator
+        .negotiate_or_first(header.as_deref())
+        .map_err(NegotiationError::NegotiationFailure)?;
+    #[cfg(feature = "otel")]
+    crate::otel::record_negotiation_on_active_span(N::KIND, res.as_str());
+    Ok(res.clone())
+}
+
+pub struct NegotiatorLayer<N: NegotiationType, T> {
+    negotiator: Arc<Negotiator<N, T>>,
+}
+
+impl<N: NegotiationType, T> NegotiatorLayer<N, T> {
+    pub fn new(negotiator: Negotiator<N, T>) -> Self {
+        Self {
+            negotiator: Arc::new(negotiator),
+        }
+    }
+}
+
+impl<N: NegotiationType, T> Clone for NegotiatorLayer<N, T> {
+    fn clone(&self) -> Self {
+        Self {
+            negotiator: Arc::clone(&self.negotiator),
+        }
+    }
+}
+
+impl<S, N: NegotiationType, T> Layer<S> for NegotiatorLayer<N, T> {
+    type Service = NegotiatorService<S, N, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        NegotiatorService {
+            inner,
+            negotiator: Arc::clone(&self.negotiator),
+        }
+    }
+}
+
+pub struct NegotiatorService<S, N: NegotiationType, T> {
+    inner: S,
+    negotiator: Arc<Negotiator<N, T>>,
+}
+
+impl<S: Clone, N: NegotiationType, T> Clone for NegotiatorService<S, N, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            negotiator: Arc::clone(&self.negotiator),
+        }
+    }
+}
+
+impl<S, B, N, T> Service<Request<B>> for NegotiatorService<S, N, T>
+where
+    S: Service<Request<B>>,
+    N: NegotiationType + Send + Sync + 'static,
+    N::Parsed: Send + Sync,
+    T: Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        req.extensions_mut().insert(Arc::clone(&self.negotiator));
+        self.inner.call(req)
+    }
+}
+
 use thiserror::Error as ThisError;
 
 #[derive(ThisError, Eq, PartialEq, Debug)]
 pub enum NegotiationError {
+    #[deprecated(note = "non UTF-8 headers are decoded as Latin-1, so this is never returned")]
     #[error("invalid accept header")]
     InvalidAcceptHeader,
     #[error("negotiation failure: {0}")]
     NegotiationFailure(Error),
+    #[error("no negotiator registered for this route")]
+    MissingNegotiator,
 }
 
 impl IntoResponse for NegotiationError {
     fn into_response(self) -> Response {
-        (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+        let status = match self {
+            NegotiationError::NegotiationFailure(Error::NotAcceptable) => {
+                StatusCode::NOT_ACCEPTABLE
+            }
+            NegotiationError::MissingNegotiator => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        (status, self.to_string()).into_response()
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct LocalizedMessages {
+    pub language: String,
+    pub not_acceptable: String,
+    pub unsupported_media_type: String,
+}
+
+impl AsNegotiationStr for LocalizedMessages {
+    fn as_str(&self) -> &str {
+        &self.language
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ErrorCatalog {
+    negotiator: Negotiator<LanguageNegotiation, LocalizedMessages>,
+}
+
+impl ErrorCatalog {
+    pub fn new<I>(messages: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = LocalizedMessages>,
+    {
+        let negotiator = Negotiator::new(messages)?;
+        if negotiator.is_empty() {
+            return Err(Error::EmptySupported);
+        }
+        Ok(Self { negotiator })
+    }
+
+    pub fn messages(&self, headers: &HeaderMap) -> &LocalizedMessages {
+        combined_header(headers, ACCEPT_LANGUAGE)
+            .and_then(|header| self.negotiator.negotiate(&header).ok().flatten())
+            .unwrap_or_else(|| self.negotiator.unwrap_first())
+    }
+
+    pub fn not_acceptable(&self, headers: &HeaderMap) -> Response {
+        let messages = self.messages(headers);
+        localized_response(
+            StatusCode::NOT_ACCEPTABLE,
+            messages,
+            &messages.not_acceptable,
+        )
+    }
+
+    pub fn unsupported_media_type(&self, headers: &HeaderMap) -> Response {
+        let messages = self.messages(headers);
+        localized_response(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            messages,
+            &messages.unsupported_media_type,
+        )
+    }
+}
+
+fn localized_response(status: StatusCode, messages: &LocalizedMessages, body: &str) -> Response {
+    let mut response = (status, body.to_owned()).into_response();
+    if let Ok(language) = content_language([&messages.language]) {
+        response.headers_mut().insert(CONTENT_LANGUAGE, language);
+    }
+    response
+}
+
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::{borrow::Cow, sync::Arc};
 
     use axum::{body::Body, routing::get, Router};
     use axum_core::{extract::FromRef, response::IntoResponse};
-    use http::{header::ACCEPT, Request, StatusCode};
+    use http::{
+        header::{ACCEPT, ACCEPT_LANGUAGE, CONTENT_LANGUAGE},
+        HeaderMap, HeaderValue, Request, StatusCode,
+    };
     use tower::ServiceExt;
 
-    use crate::{axum::Negotiation, AsNegotiationStr, ContentTypeNegotiation, Negotiator};
+    use crate::{
+        axum::{ErrorCatalog, LocalizedMessages, Negotiation, NegotiatorLayer, RouteNegotiation},
+        AsNegotiationStr, ContentTypeNegotiation, Error, Negotiator,
+    };
 
     #[derive(Clone)]
     enum Content {
@@ -121,7 +328,7 @@ mod tests {
         Negotiation(_, content): Negotiation<ContentTypeNegotiation, Content>,
     ) -> impl IntoResponse {
         match content {
-            Content::Json => format!("{{\"message\":\"hello\"}}"),
+            Content::Json => "{\"message\":\"hello\"}".to_owned(),
             Content::Text => "hello".to_owned(),
         }
     }
@@ -160,6 +367,43 @@ mod tests {
         let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
         assert_eq!(&body[..], b"hello");
 
+        // Latin-1.
+        let response = router()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(
+                        ACCEPT,
+                        HeaderValue::from_bytes(b"text/html;title=caf\xe9, application/json")
+                            .unwrap(),
+                    )
+                    .body(Body::from("hello"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"{\"message\":\"hello\"}");
+
+        // Multiple fields.
+        let response = router()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(ACCEPT, "text/html")
+                    .header(ACCEPT, "text/plain;q=0.5, application/json;q=0.1")
+                    .body(Body::from("hello"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"hello");
+
         // Default.
         let response = router()
             .oneshot(
@@ -188,4 +432,206 @@ mod tests {
             .unwrap();
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
+
+    #[tokio::test]
+    async fn not_acceptable() {
+        async fn handler(
+            RouteNegotiation(_, content): RouteNegotiation<ContentTypeNegotiation, &'static str>,
+        ) -> impl IntoResponse {
+            content
+        }
+
+        let router = Router::new().route(
+            "/",
+            get(handler).layer(NegotiatorLayer::new(
+                Negotiator::<ContentTypeNegotiation, _>::builder()
+                    .supported("text/plain")
+                    .strict(true)
+                    .build()
+                    .unwrap(),
+            )),
+        );
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(ACCEPT, "application/json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+
+        let response = router
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn explicit_default() {
+        async fn handler(
+            RouteNegotiation(_, content): RouteNegotiation<ContentTypeNegotiation, &'static str>,
+        ) -> impl IntoResponse {
+            content
+        }
+
+        let router = Router::new().route(
+            "/",
+            get(handler).layer(NegotiatorLayer::new(
+                Negotiator::<ContentTypeNegotiation, _>::new(["text/html", "application/json"])
+                    .unwrap()
+                    .with_default("application/json"),
+            )),
+        );
+
+        for accept in ["*/*", "image/png"] {
+            let response = router
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri("/")
+                        .header(ACCEPT, accept)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            assert_eq!(&body[..], b"application/json");
+        }
+    }
+
+    #[tokio::test]
+    async fn borrowed() {
+        static SUPPORTED: &[&str] = &["text/plain", "application/json"];
+
+        async fn handler(
+            RouteNegotiation(_, content): RouteNegotiation<
+                ContentTypeNegotiation,
+                Cow<'static, str>,
+            >,
+        ) -> impl IntoResponse {
+            content
+        }
+
+        let response = Router::new()
+            .route(
+                "/",
+                get(handler).layer(NegotiatorLayer::new(
+                    Negotiator::<ContentTypeNegotiation, _>::borrowed(SUPPORTED).unwrap(),
+                )),
+            )
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(ACCEPT, "application/json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"application/json");
+    }
+
+    #[tokio::test]
+    async fn error_catalog() {
+        let catalog = ErrorCatalog::new([
+            LocalizedMessages {
+                language: "en-US".to_owned(),
+                not_acceptable: "not acceptable".to_owned(),
+                unsupported_media_type: "unsupported media type".to_owned(),
+            },
+            LocalizedMessages {
+                language: "fr-FR".to_owned(),
+                not_acceptable: "non acceptable".to_owned(),
+                unsupported_media_type: "type de média non supporté".to_owned(),
+            },
+        ])
+        .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("fr, en;q=0.5"));
+        let response = catalog.not_acceptable(&headers);
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(response.headers()[CONTENT_LANGUAGE], "fr-FR");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"non acceptable");
+
+        let response = catalog.unsupported_media_type(&HeaderMap::new());
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(response.headers()[CONTENT_LANGUAGE], "en-US");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"unsupported media type");
+
+        assert_eq!(ErrorCatalog::new([]).unwrap_err(), Error::EmptySupported);
+    }
+
+    #[cfg(feature = "macros")]
+    #[tokio::test]
+    async fn produces() {
+        use crate::produces;
+
+        #[produces("text/csv", "application/json", route = get("/export"))]
+        async fn export(
+            RouteNegotiation(_, content): RouteNegotiation<ContentTypeNegotiation, &'static str>,
+        ) -> impl IntoResponse {
+            content
+        }
+
+        async fn missing(
+            RouteNegotiation(_, content): RouteNegotiation<ContentTypeNegotiation, &'static str>,
+        ) -> impl IntoResponse {
+            content
+        }
+
+        let router = export_route(Router::new().route("/missing", get(missing)));
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/export")
+                    .header(ACCEPT, "application/json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"application/json");
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/export")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"text/csv");
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .uri("/missing")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
 }
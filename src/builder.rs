@@ -0,0 +1,1200 @@
+use std::{cmp::Ordering, marker::PhantomData, sync::Arc};
+
+use crate::{
+    outcome::OutcomeHook, AsNegotiationStr, Availability, Clock, DefaultValue, Entry, Error,
+    NegotiationOutcome, NegotiationType, Negotiator, Quality,
+};
+
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum WildcardPolicy {
+    #[default]
+    Allow,
+    Ignore,
+    Reject,
+}
+
+/// Orders supported values having the same quality and weight.
+#[derive(Copy, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum TieBreak {
+    /// The value matched by the range the client prefers wins.
+    #[default]
+    ClientOrder,
+    /// The value listed first on the negotiator wins.
+    ServerOrder,
+    /// The value with the most specific supported form (e.g. the most parameters) wins, then
+    /// client order.
+    MostSpecific,
+    /// The value ordered first by the comparator wins, then server order.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(fn(&str, &str) -> Ordering),
+}
+
+/// What to do with a supported value whose parsed form is already supported.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum DuplicatePolicy {
+    /// Keep both, the later one can only be selected through `negotiate_all`.
+    #[default]
+    Keep,
+    /// Fail with [`Error::DuplicateSupported`].
+    Error,
+    /// Keep the first value.
+    Ignore,
+    /// Replace the first value in place with the later one.
+    LastWins,
+}
+
+impl DuplicatePolicy {
+    pub(crate) fn push<P: PartialEq, T>(
+        self,
+        supported: &mut Vec<Entry<P, T>>,
+        entry: Entry<P, T>,
+    ) -> Result<(), Error> {
+        self.insert(supported, supported.len(), entry)
+    }
+
+    /// Inserts the entry at `index` unless it duplicates one, a replaced duplicate keeping its
+    /// position.
+    pub(crate) fn insert<P: PartialEq, T>(
+        self,
+        supported: &mut Vec<Entry<P, T>>,
+        index: usize,
+        entry: Entry<P, T>,
+    ) -> Result<(), Error> {
+        let Some(i) = supported.iter().position(|e| e.parsed == entry.parsed) else {
+            supported.insert(index, entry);
+            return Ok(());
+        };
+        match self {
+            DuplicatePolicy::Keep => supported.insert(index, entry),
+            DuplicatePolicy::Error => return Err(Error::DuplicateSupported),
+            DuplicatePolicy::Ignore => {}
+            DuplicatePolicy::LastWins => supported[i] = entry,
+        }
+        Ok(())
+    }
+}
+
+/// How the parameters of a header range are compared to the ones of a supported value.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ParamMatching {
+    /// A concrete range must carry exactly the supported parameters, while the parameters of a
+    /// wildcard range must all be supported.
+    #[default]
+    Exact,
+    /// Every supported parameter must be in the range, which may carry other ones (e.g. a
+    /// supported `text/html` matches `text/html;charset=utf-8`).
+    SupportedSubset,
+    /// Supported parameters are attributes of the response, only failing the match when the range
+    /// asks for another value (e.g. a supported `text/html;level=1` matches `text/html` and
+    /// `text/html;level=1;charset=utf-8`, but not `text/html;level=2`).
+    Compatible,
+    /// Parameters are not compared.
+    Ignore,
+}
+
+/// Parameter options passed down to [`NegotiationType::matches_with`].
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+pub struct MatchOptions {
+    pub decode_params: bool,
+    pub params: ParamMatching,
+}
+
+/// How the quality of a supported value is picked among the header ranges matching it.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Scoring {
+    /// The quality of the most specific matching range, as defined by RFC 9110.
+    #[default]
+    Rfc9110,
+    /// The highest quality of any matching range.
+    FirstMatch,
+}
+
+/// What to do with the header parameters no supported value declares, e.g. `random` in
+/// `text/html;random=1` when only `text/html` is supported.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum UnknownParams {
+    /// Match them like any other parameter, following [`ParamMatching`].
+    #[default]
+    RequireMatch,
+    /// Match as if the range did not have them.
+    Ignore,
+    /// Fail with [`Error::UnknownParameter`].
+    Error,
+}
+
+/// How the client quality and the server weight of a supported value combine to rank it.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Weighting {
+    /// The client quality wins, the server weight only orders values of equal quality.
+    #[default]
+    ClientFirst,
+    /// The product of both wins, as with Apache content negotiation.
+    Multiplicative,
+}
+
+/// Bounds on the work done parsing a header, exceeding any of them fails with
+/// [`Error::LimitExceeded`]. Everything is unbounded by default.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "kebab-case"))]
+pub struct Limits {
+    /// Maximum length of the header, in bytes.
+    pub max_length: usize,
+    /// Maximum number of comma-separated elements.
+    pub max_elements: usize,
+    /// Maximum number of parameters of an element, quality included.
+    pub max_params: usize,
+}
+
+impl Limits {
+    pub const UNBOUNDED: Self = Self {
+        max_length: usize::MAX,
+        max_elements: usize::MAX,
+        max_params: usize::MAX,
+    };
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::UNBOUNDED
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub(crate) struct Options {
+    pub(crate) strict: bool,
+    pub(crate) wildcard: WildcardPolicy,
+    pub(crate) tie_break: TieBreak,
+    pub(crate) scoring: Scoring,
+    pub(crate) weighting: Weighting,
+    pub(crate) unknown_params: UnknownParams,
+    pub(crate) matching: MatchOptions,
+    pub(crate) lenient: bool,
+    pub(crate) duplicates: DuplicatePolicy,
+    pub(crate) limits: Limits,
+    pub(crate) strict_quality: bool,
+    pub(crate) browser_quirks: bool,
+    pub(crate) suffix_matching: bool,
+    pub(crate) wildcard_supported: bool,
+    pub(crate) on_outcome: Option<OutcomeHook>,
+    #[cfg(feature = "http")]
+    pub(crate) header: Option<http::header::HeaderName>,
+}
+
+impl Options {
+    pub(crate) fn parse_supported<N, M>(&self, value: &M) -> Result<N::Parsed, Error>
+    where
+        N: NegotiationType,
+        M: AsNegotiationStr,
+    {
+        match self.wildcard_supported {
+            true => N::parse_wildcard_elem(value),
+            false => N::parse_elem(value),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NegotiatorBuilder<N, T> {
+    supported: Vec<(T, Result<Quality, Error>, Availability)>,
+    default: Option<T>,
+    aliases: Vec<(String, String)>,
+    fallbacks: Vec<Vec<String>>,
+    options: Options,
+    clock: Option<Arc<dyn Clock>>,
+    _negotiation: PhantomData<N>,
+}
+
+impl<N, T> Default for NegotiatorBuilder<N, T> {
+    fn default() -> Self {
+        Self {
+            supported: Vec::new(),
+            default: None,
+            aliases: Vec::new(),
+            fallbacks: Vec::new(),
+            options: Options::default(),
+            clock: None,
+            _negotiation: PhantomData,
+        }
+    }
+}
+
+impl<N, T> NegotiatorBuilder<N, T>
+where
+    N: NegotiationType,
+    T: AsNegotiationStr,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn supported(mut self, value: T) -> Self {
+        self.supported
+            .push((value, Ok(Quality::ONE), Availability::always()));
+        self
+    }
+
+    pub fn supported_weighted<W>(mut self, value: T, weight: W) -> Self
+    where
+        W: TryInto<Quality>,
+        Error: From<W::Error>,
+    {
+        self.supported.push((
+            value,
+            weight.try_into().map_err(Error::from),
+            Availability::always(),
+        ));
+        self
+    }
+
+    pub fn supported_between(mut self, value: T, availability: Availability) -> Self {
+        self.supported.push((value, Ok(Quality::ONE), availability));
+        self
+    }
+
+    pub fn extend<I>(mut self, iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        self.supported.extend(
+            iter.into_iter()
+                .map(|v| (v, Ok(Quality::ONE), Availability::always())),
+        );
+        self
+    }
+
+    pub fn default_value(mut self, value: T) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    /// Lets ranges matching `alias`, but no wildcard range, match the supported values equal to
+    /// `target`, e.g. `text/json` for `application/json`. Aliases only go one way.
+    pub fn alias(mut self, alias: &str, target: &str) -> Self {
+        self.aliases.push((alias.to_owned(), target.to_owned()));
+        self
+    }
+
+    /// Adds every alias and target pair, see
+    /// [`media_types::LEGACY_ALIASES`](crate::media_types::LEGACY_ALIASES).
+    pub fn aliases<'a, I>(mut self, iter: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        self.aliases
+            .extend(iter.into_iter().map(|(a, t)| (a.to_owned(), t.to_owned())));
+        self
+    }
+
+    /// Adds a fallback chain, e.g. `["pt-BR", "pt", "en"]`, used when no range of the header
+    /// matches anything. Each range, in quality order, is retried as the values of the first chain
+    /// covering it, from the first covering one onward, so `pt-PT` is retried as `pt` then `en`.
+    pub fn fallback<'a, I>(mut self, chain: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.fallbacks
+            .push(chain.into_iter().map(str::to_owned).collect());
+        self
+    }
+
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.strict = strict;
+        self
+    }
+
+    pub fn wildcard_policy(mut self, policy: WildcardPolicy) -> Self {
+        self.options.wildcard = policy;
+        self
+    }
+
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.options.tie_break = tie_break;
+        self
+    }
+
+    pub fn scoring(mut self, scoring: Scoring) -> Self {
+        self.options.scoring = scoring;
+        self
+    }
+
+    pub fn weighting(mut self, weighting: Weighting) -> Self {
+        self.options.weighting = weighting;
+        self
+    }
+
+    pub fn unknown_params(mut self, policy: UnknownParams) -> Self {
+        self.options.unknown_params = policy;
+        self
+    }
+
+    /// Skips malformed header elements instead of failing the whole negotiation.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.options.lenient = lenient;
+        self
+    }
+
+    /// Matches header parameter values once percent-decoded (or RFC 8187 decoded for `name*`
+    /// parameters) instead of comparing their raw form.
+    pub fn decode_params(mut self, decode: bool) -> Self {
+        self.options.matching.decode_params = decode;
+        self
+    }
+
+    pub fn param_matching(mut self, policy: ParamMatching) -> Self {
+        self.options.matching.params = policy;
+        self
+    }
+
+    /// Allows wildcard supported values (e.g. `image/*`), matching any header range of the same
+    /// type. They are ranked like any other value, so list them after the concrete ones.
+    pub fn wildcard_supported(mut self, allowed: bool) -> Self {
+        self.options.wildcard_supported = allowed;
+        self
+    }
+
+    /// Lets a subtype match its structured syntax suffix, in both directions: a supported
+    /// `application/json` is selected for `application/problem+json`, and a supported
+    /// `application/vnd.api+json` for `application/json`.
+    pub fn suffix_matching(mut self, enabled: bool) -> Self {
+        self.options.suffix_matching = enabled;
+        self
+    }
+
+    /// Rejects header qualities not following the RFC 9110 grammar (between 0 and 1 with at most
+    /// three decimals) instead of clamping them.
+    pub fn strict_quality(mut self, strict: bool) -> Self {
+        self.options.strict_quality = strict;
+        self
+    }
+
+    /// Accepts the malformed headers sent by some old and embedded browsers instead of failing
+    /// them: empty elements, a bare `*` for `*/*`, spaces around the `/` of a media range and
+    /// around the `=` of a quality without parameters.
+    pub fn browser_quirks(mut self, enabled: bool) -> Self {
+        self.options.browser_quirks = enabled;
+        self
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// What to do with supported values parsing to an already supported one.
+    pub fn duplicates(mut self, policy: DuplicatePolicy) -> Self {
+        self.options.duplicates = policy;
+        self
+    }
+
+    /// Negotiates on `header` instead of the one associated with the negotiation type.
+    #[cfg(feature = "http")]
+    pub fn header(mut self, header: http::header::HeaderName) -> Self {
+        self.options.header = Some(header);
+        self
+    }
+
+    /// See [`Negotiator::on_outcome`].
+    pub fn on_outcome<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&NegotiationOutcome<'_>) + Send + Sync + 'static,
+    {
+        self.options.on_outcome = Some(OutcomeHook::new(hook));
+        self
+    }
+
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    pub fn build(self) -> Result<Negotiator<N, T>, Error>
+    where
+        N::Parsed: PartialEq,
+    {
+        let mut supported = Vec::with_capacity(self.supported.len());
+        for (m, weight, availability) in self.supported {
+            let entry = Entry {
+                weight: weight?,
+                availability,
+                ..Entry::new(self.options.parse_supported::<N, _>(&m)?, m)
+            };
+            self.options.duplicates.push(&mut supported, entry)?;
+        }
+        let aliases = self
+            .aliases
+            .iter()
+            .map(|(alias, target)| Ok((N::parse_elem(alias)?, N::parse_elem(target)?)))
+            .collect::<Result<_, Error>>()?;
+        let fallbacks = self
+            .fallbacks
+            .into_iter()
+            .map(|chain| {
+                chain
+                    .into_iter()
+                    .map(|value| Ok((N::parse_elem(&value)?, value)))
+                    .collect()
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Negotiator {
+            supported: Arc::new(supported.into()),
+            default: self.default.map(|default| {
+                let parsed = self.options.parse_supported::<N, _>(&default).ok();
+                Arc::new(DefaultValue::new(default, parsed))
+            }),
+            aliases,
+            fallbacks,
+            options: self.options,
+            clock: self.clock,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::{
+        DuplicatePolicy, Limits, ParamMatching, Scoring, TieBreak, UnknownParams, Weighting,
+        WildcardPolicy,
+    };
+    use crate::{
+        ContentTypeNegotiation, EncodingNegotiation, Error, LanguageNegotiation, Negotiator,
+        ParseQualityError, Quality,
+    };
+
+    #[test]
+    fn build() {
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .supported("text/html")
+            .supported_weighted("application/json", 0.5)
+            .build()
+            .unwrap();
+        assert_eq!(negotiator.len(), 2);
+        assert_eq!(negotiator.default_value(), Some(&"text/html"));
+
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .extend(["text/html", "text/*"])
+                .build()
+                .unwrap_err(),
+            Error::InvalidWildcard
+        );
+
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .supported_weighted("text/html", 2.)
+                .build()
+                .unwrap_err(),
+            Error::InvalidWeight
+        );
+    }
+
+    #[test]
+    fn default_value() {
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .extend(["text/html", "application/json"])
+            .default_value("application/json")
+            .build()
+            .unwrap();
+        assert_eq!(negotiator.default_value(), Some(&"application/json"));
+        assert_eq!(
+            negotiator.negotiate("text/html").unwrap(),
+            Some(&"text/html")
+        );
+
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, &str>::builder()
+                .build()
+                .unwrap()
+                .default_value(),
+            None
+        );
+    }
+
+    #[test]
+    fn duplicates() {
+        let builder = |policy| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .supported_weighted("text/html", 0.5)
+                .supported("application/json")
+                .supported("text/html")
+                .duplicates(policy)
+                .build()
+        };
+        assert_eq!(builder(DuplicatePolicy::Keep).unwrap().len(), 3);
+        assert_eq!(
+            builder(DuplicatePolicy::Error).unwrap_err(),
+            Error::DuplicateSupported
+        );
+
+        let negotiator = builder(DuplicatePolicy::Ignore).unwrap();
+        assert_eq!(negotiator.len(), 2);
+        assert_eq!(
+            negotiator.negotiate("text/html, application/json").unwrap(),
+            Some(&"application/json")
+        );
+
+        let negotiator = builder(DuplicatePolicy::LastWins).unwrap();
+        assert_eq!(
+            negotiator.supported().collect::<Vec<_>>(),
+            vec![&"text/html", &"application/json"]
+        );
+        assert_eq!(
+            negotiator.negotiate("text/html, application/json").unwrap(),
+            Some(&"text/html")
+        );
+    }
+
+    #[test]
+    fn duplicates_mutations() {
+        let negotiator = |policy| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .extend(["text/html", "application/json"])
+                .duplicates(policy)
+                .build()
+                .unwrap()
+        };
+        let supported = |negotiator: &Negotiator<_, &str>| negotiator.supported().count();
+
+        let mut keep = negotiator(DuplicatePolicy::Keep);
+        keep.push("text/html").unwrap();
+        keep.insert(0, "application/json").unwrap();
+        keep.try_extend(["text/plain", "text/plain"]).unwrap();
+        assert_eq!(supported(&keep), 6);
+
+        let mut error = negotiator(DuplicatePolicy::Error);
+        assert_eq!(error.push("text/html"), Err(Error::DuplicateSupported));
+        assert_eq!(
+            error.insert(0, "application/json"),
+            Err(Error::DuplicateSupported)
+        );
+        assert_eq!(
+            error.try_extend(["text/plain", "text/html"]),
+            Err(Error::DuplicateSupported)
+        );
+        assert_eq!(
+            error.try_extend(["text/plain", "text/plain"]),
+            Err(Error::DuplicateSupported)
+        );
+        assert_eq!(supported(&error), 2);
+
+        let mut ignore = negotiator(DuplicatePolicy::Ignore);
+        ignore.push("text/html").unwrap();
+        ignore.insert(0, "application/json").unwrap();
+        ignore.try_extend(["text/plain", "text/html"]).unwrap();
+        assert_eq!(
+            ignore.supported().collect::<Vec<_>>(),
+            vec![&"text/html", &"application/json", &"text/plain"]
+        );
+
+        let mut last_wins = Negotiator::<ContentTypeNegotiation, String>::builder()
+            .extend(["text/html".to_owned(), "application/json".to_owned()])
+            .duplicates(DuplicatePolicy::LastWins)
+            .build()
+            .unwrap();
+        last_wins.push("TEXT/HTML".to_owned()).unwrap();
+        last_wins.insert(0, "Application/JSON".to_owned()).unwrap();
+        last_wins
+            .try_extend(["text/plain".to_owned(), "Text/Plain".to_owned()])
+            .unwrap();
+        assert_eq!(
+            last_wins.supported().collect::<Vec<_>>(),
+            vec!["TEXT/HTML", "Application/JSON", "Text/Plain"]
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .supported("text/html")
+            .limits(Limits {
+                max_length: 64,
+                max_elements: 3,
+                max_params: 2,
+            })
+            .lenient(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            negotiator
+                .negotiate("text/plain;q=1, text/*;q=0.5")
+                .unwrap(),
+            Some(&"text/html")
+        );
+        assert_eq!(
+            negotiator.negotiate(&"text/html,".repeat(10)).unwrap_err(),
+            Error::LimitExceeded
+        );
+        assert_eq!(
+            negotiator
+                .negotiate("a/a, b/b, c/c, text/html")
+                .unwrap_err(),
+            Error::LimitExceeded
+        );
+        assert_eq!(
+            negotiator.negotiate("text/html;a=1;b=2;q=1").unwrap_err(),
+            Error::LimitExceeded
+        );
+    }
+
+    #[test]
+    fn param_matching() {
+        let negotiator = |policy| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .extend(["text/html;level=1", "application/json"])
+                .param_matching(policy)
+                .build()
+                .unwrap()
+        };
+        let header = "text/html;level=1;mode=full, application/json;q=0.5";
+        assert_eq!(
+            negotiator(ParamMatching::Exact).negotiate(header).unwrap(),
+            Some(&"application/json")
+        );
+        assert_eq!(
+            negotiator(ParamMatching::SupportedSubset)
+                .negotiate(header)
+                .unwrap(),
+            Some(&"text/html;level=1")
+        );
+        assert_eq!(
+            negotiator(ParamMatching::SupportedSubset)
+                .negotiate("text/html, application/json;charset=utf-8;q=0.5")
+                .unwrap(),
+            Some(&"application/json")
+        );
+        assert_eq!(
+            negotiator(ParamMatching::Compatible)
+                .negotiate("text/html, application/json;q=0.5")
+                .unwrap(),
+            Some(&"text/html;level=1")
+        );
+        assert_eq!(
+            negotiator(ParamMatching::Compatible)
+                .negotiate(header)
+                .unwrap(),
+            Some(&"text/html;level=1")
+        );
+        assert_eq!(
+            negotiator(ParamMatching::Compatible)
+                .negotiate("text/html;level=2, application/json;q=0.5")
+                .unwrap(),
+            Some(&"application/json")
+        );
+        assert_eq!(
+            negotiator(ParamMatching::Ignore)
+                .negotiate("text/html;level=2, application/json;q=0.5")
+                .unwrap(),
+            Some(&"text/html;level=1")
+        );
+    }
+
+    #[test]
+    fn wildcard_supported() {
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .supported("image/*")
+                .build()
+                .unwrap_err(),
+            Error::InvalidWildcard
+        );
+
+        let mut negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .extend(["image/webp", "image/*"])
+            .wildcard_supported(true)
+            .build()
+            .unwrap();
+        assert_eq!(negotiator.negotiate("image/png").unwrap(), Some(&"image/*"));
+        assert_eq!(
+            negotiator.negotiate("image/webp, image/png").unwrap(),
+            Some(&"image/webp")
+        );
+        assert_eq!(negotiator.negotiate("*/*").unwrap(), Some(&"image/webp"));
+        assert!(negotiator.negotiate("text/html").unwrap().is_none());
+
+        negotiator.push("*/*").unwrap();
+        assert_eq!(negotiator.negotiate("text/html").unwrap(), Some(&"*/*"));
+        assert_eq!(
+            negotiator.push("*/html").unwrap_err(),
+            Error::InvalidWildcard
+        );
+        assert_eq!(
+            negotiator.push("text/*;q=1").unwrap_err(),
+            Error::QualityNotAllowed
+        );
+    }
+
+    #[test]
+    fn suffix_matching() {
+        let negotiator = |supported, enabled| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .supported(supported)
+                .suffix_matching(enabled)
+                .build()
+                .unwrap()
+        };
+        assert!(negotiator("application/json", false)
+            .negotiate("application/problem+json")
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            negotiator("application/json", true)
+                .negotiate("application/problem+json")
+                .unwrap(),
+            Some(&"application/json")
+        );
+        assert_eq!(
+            negotiator("application/vnd.me.v1+json", true)
+                .negotiate("application/json")
+                .unwrap(),
+            Some(&"application/vnd.me.v1+json")
+        );
+        assert!(negotiator("application/json", true)
+            .negotiate("application/problem+xml, text/json")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn unknown_params() {
+        let negotiator = |policy| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .extend(["text/html", "text/plain;format=flowed"])
+                .unknown_params(policy)
+                .build()
+                .unwrap()
+        };
+        let header = "text/html;random=1";
+        assert_eq!(
+            negotiator(UnknownParams::RequireMatch)
+                .negotiate(header)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            negotiator(UnknownParams::Ignore).negotiate(header).unwrap(),
+            Some(&"text/html")
+        );
+        assert_eq!(
+            negotiator(UnknownParams::Error)
+                .negotiate(header)
+                .unwrap_err(),
+            Error::UnknownParameter
+        );
+
+        // Known parameters still have to match.
+        assert_eq!(
+            negotiator(UnknownParams::Ignore)
+                .negotiate("text/plain;format=fixed;random=1")
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            negotiator(UnknownParams::Ignore)
+                .negotiate_all("text/plain;format=flowed;random=1, */*;q=0.5")
+                .unwrap(),
+            vec![&"text/plain;format=flowed", &"text/html"]
+        );
+        assert_eq!(
+            negotiator(UnknownParams::Error)
+                .negotiate("text/plain;Format=flowed")
+                .unwrap(),
+            Some(&"text/plain;format=flowed")
+        );
+    }
+
+    #[test]
+    fn weighting() {
+        let negotiator = |weighting| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .supported_weighted("text/html", 0.5)
+                .supported("application/json")
+                .weighting(weighting)
+                .build()
+                .unwrap()
+        };
+        let header = "text/html, application/json;q=0.8";
+        assert_eq!(
+            negotiator(Weighting::ClientFirst)
+                .negotiate(header)
+                .unwrap(),
+            Some(&"text/html")
+        );
+        assert_eq!(
+            negotiator(Weighting::Multiplicative)
+                .negotiate(header)
+                .unwrap(),
+            Some(&"application/json")
+        );
+        assert_eq!(
+            negotiator(Weighting::Multiplicative)
+                .negotiate_all(header)
+                .unwrap(),
+            vec![&"application/json", &"text/html"]
+        );
+        assert_eq!(
+            negotiator(Weighting::Multiplicative)
+                .negotiate("*/*")
+                .unwrap(),
+            Some(&"application/json")
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .extend(["text/html", "application/json"])
+            .alias("text/json", "application/json")
+            .aliases([("application/x-json", "application/json")])
+            .build()
+            .unwrap();
+        for header in [
+            "text/json",
+            "application/x-json",
+            "text/json;q=0.9, text/html;q=0.5",
+        ] {
+            assert_eq!(
+                negotiator.negotiate(header).unwrap(),
+                Some(&"application/json")
+            );
+        }
+        assert_eq!(negotiator.negotiate("text/*").unwrap(), Some(&"text/html"));
+        assert_eq!(
+            negotiator.negotiate("text/json;q=0, text/*").unwrap(),
+            Some(&"text/html")
+        );
+
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .supported("text/json")
+            .alias("text/json", "application/json")
+            .build()
+            .unwrap();
+        assert_eq!(negotiator.negotiate("application/json").unwrap(), None);
+
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, &str>::builder()
+                .alias("json", "application/json")
+                .build()
+                .unwrap_err(),
+            Error::MissingSeparator('/')
+        );
+    }
+
+    #[test]
+    fn fallbacks() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::builder()
+            .extend(["en-US", "pt-BR"])
+            .fallback(["pt-BR", "pt", "en"])
+            .default_value("en-US")
+            .build()
+            .unwrap();
+        assert_eq!(negotiator.negotiate("pt-PT").unwrap(), Some(&"pt-BR"));
+        assert_eq!(negotiator.negotiate("pt").unwrap(), Some(&"pt-BR"));
+        assert_eq!(
+            negotiator.negotiate("pt-PT, en-US;q=0.1").unwrap(),
+            Some(&"en-US")
+        );
+        assert_eq!(
+            negotiator.negotiate("pt-PT, pt-BR;q=0").unwrap(),
+            Some(&"en-US")
+        );
+        assert_eq!(
+            negotiator
+                .negotiate_detailed("fr, pt-PT;q=0.5")
+                .unwrap()
+                .map(|n| (n.range, n.quality)),
+            Some(("pt-PT".to_owned(), Quality::from_thousandths(500).unwrap()))
+        );
+        assert_eq!(negotiator.negotiate("fr").unwrap(), Some(&"en-US"));
+
+        assert_eq!(
+            Negotiator::<LanguageNegotiation, &str>::builder()
+                .fallback(["pt", "en;q=1"])
+                .build()
+                .unwrap_err(),
+            Error::ParamsNotAllowed
+        );
+    }
+
+    #[test]
+    fn browser_quirks() {
+        let negotiator = |quirks| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .extend(["text/html", "application/json"])
+                .browser_quirks(quirks)
+                .build()
+                .unwrap()
+        };
+        for header in [
+            "*;q=0.5, text/html",
+            "text / html, */*;q=0.5",
+            "text/html, application/json;Q=0.5",
+            "text/html,, */*;q=0.5,",
+        ] {
+            assert_eq!(
+                negotiator(true).negotiate(header).unwrap(),
+                Some(&"text/html")
+            );
+        }
+        assert_eq!(
+            negotiator(false)
+                .negotiate("*;q=0.5, text/html")
+                .unwrap_err(),
+            Error::MissingSeparator('/')
+        );
+        assert!(negotiator(false).negotiate("text/html,, */*").is_err());
+        assert!(negotiator(true).negotiate("*/html").is_err());
+
+        let negotiator = |quirks| {
+            Negotiator::<EncodingNegotiation, _>::builder()
+                .extend(["gzip", "br"])
+                .browser_quirks(quirks)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(
+            negotiator(true)
+                .negotiate("gzip;Q=0.5, br ; q = 0.8")
+                .unwrap(),
+            Some(&"br")
+        );
+        assert_eq!(
+            negotiator(false).negotiate("gzip;Q=0.5, br;q=0.8").unwrap(),
+            Some(&"br")
+        );
+        assert_eq!(
+            negotiator(false).negotiate("br ; q = 0.8").unwrap_err(),
+            Error::ParamsNotAllowed
+        );
+    }
+
+    #[test]
+    fn strict_quality() {
+        let negotiator = |strict| {
+            Negotiator::<EncodingNegotiation, _>::builder()
+                .extend(["gzip", "br"])
+                .strict_quality(strict)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(
+            negotiator(false)
+                .negotiate("gzip;q=0.1234, br;q=5")
+                .unwrap(),
+            Some(&"br")
+        );
+        for (header, source) in [
+            ("gzip;q=5", ParseQualityError::OutOfRange),
+            ("gzip;q=-1", ParseQualityError::Malformed),
+            ("gzip;q=0.1234", ParseQualityError::TooManyDecimals),
+            ("gzip;Q=1.5", ParseQualityError::OutOfRange),
+        ] {
+            assert_eq!(
+                negotiator(true).negotiate(header).unwrap_err(),
+                Error::InvalidQuality { source }
+            );
+        }
+        assert_eq!(
+            negotiator(true)
+                .negotiate("gzip;q=0.123, br;q=1.000")
+                .unwrap(),
+            Some(&"br")
+        );
+    }
+
+    #[test]
+    fn strict() {
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .supported("text/html")
+            .strict(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            negotiator.negotiate("application/json").unwrap_err(),
+            Error::NotAcceptable
+        );
+        assert_eq!(negotiator.negotiate("text/*").unwrap(), Some(&"text/html"));
+        assert!(negotiator
+            .negotiate_all("application/json")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn wildcard_policy() {
+        let negotiator = |policy| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .extend(["text/html", "application/json"])
+                .wildcard_policy(policy)
+                .build()
+                .unwrap()
+        };
+        let header = "image/png, */*;q=0.5";
+
+        assert_eq!(
+            negotiator(WildcardPolicy::Allow).negotiate(header).unwrap(),
+            Some(&"text/html")
+        );
+        assert_eq!(
+            negotiator(WildcardPolicy::Ignore)
+                .negotiate(header)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            negotiator(WildcardPolicy::Reject)
+                .negotiate(header)
+                .unwrap_err(),
+            Error::InvalidWildcard
+        );
+
+        assert_eq!(
+            Negotiator::<EncodingNegotiation, _>::builder()
+                .supported("gzip")
+                .wildcard_policy(WildcardPolicy::Ignore)
+                .build()
+                .unwrap()
+                .negotiate("*")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn tie_break() {
+        let negotiator = |tie_break| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .extend(["application/json", "text/html"])
+                .tie_break(tie_break)
+                .build()
+                .unwrap()
+        };
+        let header = "text/html, application/json";
+
+        assert_eq!(
+            negotiator(TieBreak::ClientOrder).negotiate(header).unwrap(),
+            Some(&"text/html")
+        );
+        assert_eq!(
+            negotiator(TieBreak::ServerOrder).negotiate(header).unwrap(),
+            Some(&"application/json")
+        );
+        assert_eq!(
+            negotiator(TieBreak::ServerOrder)
+                .negotiate("text/html, application/json;q=0.9")
+                .unwrap(),
+            Some(&"text/html")
+        );
+
+        let negotiator = |tie_break| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .extend(["text/html", "text/html;level=1", "text/plain"])
+                .tie_break(tie_break)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(
+            negotiator(TieBreak::ClientOrder)
+                .negotiate("text/*")
+                .unwrap(),
+            Some(&"text/html")
+        );
+        assert_eq!(
+            negotiator(TieBreak::MostSpecific)
+                .negotiate("text/*")
+                .unwrap(),
+            Some(&"text/html;level=1")
+        );
+        assert_eq!(
+            negotiator(TieBreak::Custom(|a, b| b.cmp(a)))
+                .negotiate_all("text/*")
+                .unwrap(),
+            vec![&"text/plain", &"text/html;level=1", &"text/html"]
+        );
+        assert_eq!(
+            negotiator(TieBreak::Custom(|_, _| Ordering::Equal))
+                .negotiate_all("text/plain;q=0.5, text/*")
+                .unwrap(),
+            vec![&"text/html", &"text/html;level=1", &"text/plain"]
+        );
+    }
+
+    #[test]
+    fn lenient() {
+        let negotiator = |lenient| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .extend(["text/html", "application/json"])
+                .lenient(lenient)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(
+            negotiator(false)
+                .negotiate("application/json, */;q=0.8")
+                .unwrap_err(),
+            Error::InvalidWildcard
+        );
+        assert_eq!(
+            negotiator(true)
+                .negotiate("application/json, */;q=0.8")
+                .unwrap(),
+            Some(&"application/json")
+        );
+        assert_eq!(
+            negotiator(false)
+                .negotiate_lenient("image, text/*;q=0.5, ")
+                .unwrap(),
+            (
+                Some(&"text/html"),
+                vec![
+                    (0, Error::MissingSeparator('/')),
+                    (2, Error::MissingSeparator('/'))
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn scoring() {
+        let negotiator = |scoring| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .extend(["text/html", "text/plain"])
+                .scoring(scoring)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(
+            negotiator(Scoring::Rfc9110)
+                .negotiate_with_quality("text/*;q=1, text/html;q=0.5")
+                .unwrap(),
+            Some((&"text/plain", Quality::ONE))
+        );
+        assert_eq!(
+            negotiator(Scoring::Rfc9110)
+                .negotiate_all("text/*;q=1, text/html;q=0.5")
+                .unwrap(),
+            vec![&"text/plain", &"text/html"]
+        );
+        assert_eq!(
+            negotiator(Scoring::FirstMatch)
+                .negotiate_with_quality("text/*;q=1, text/html;q=0.5")
+                .unwrap(),
+            Some((&"text/html", Quality::ONE))
+        );
+    }
+}
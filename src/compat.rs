@@ -0,0 +1,181 @@
+//! Differential testing against other negotiation implementations.
+//!
+//! Reference outcomes are expressed as [`ReferenceCase`]s, either the embedded vectors taken from
+//! the `negotiator` npm package test suite or vectors exported from any other implementation
+//! (e.g. a Go or Node gateway being migrated), and replayed through this crate by [`compare`].
+
+use crate::{negotiate_dyn, Error, NegotiationKind};
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ReferenceCase {
+    pub source: String,
+    pub kind: NegotiationKind,
+    pub supported: Vec<String>,
+    pub header: String,
+    pub expected: Option<String>,
+}
+
+impl ReferenceCase {
+    pub fn new(
+        source: &str,
+        kind: NegotiationKind,
+        supported: &[&str],
+        header: &str,
+        expected: Option<&str>,
+    ) -> Self {
+        Self {
+            source: source.to_owned(),
+            kind,
+            supported: supported.iter().map(|s| (*s).to_owned()).collect(),
+            header: header.to_owned(),
+            expected: expected.map(str::to_owned),
+        }
+    }
+
+    pub fn run(&self) -> Result<Option<String>, Error> {
+        Ok(negotiate_dyn(self.kind, &self.supported, &self.header)?.cloned())
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Mismatch {
+    pub case: ReferenceCase,
+    pub actual: Result<Option<String>, Error>,
+}
+
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct Report {
+    pub total: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Report {
+    pub fn is_success(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+pub fn compare<'a, I>(cases: I) -> Report
+where
+    I: IntoIterator<Item = &'a ReferenceCase>,
+{
+    let mut report = Report::default();
+    for case in cases {
+        report.total += 1;
+        let actual = case.run();
+        if actual.as_ref() != Ok(&case.expected) {
+            report.mismatches.push(Mismatch {
+                case: case.clone(),
+                actual,
+            });
+        }
+    }
+    report
+}
+
+pub fn negotiator_npm_cases() -> Vec<ReferenceCase> {
+    use NegotiationKind::*;
+
+    const SOURCE: &str = "negotiator (npm)";
+    vec![
+        ReferenceCase::new(
+            SOURCE,
+            ContentType,
+            &["text/html"],
+            "text/html",
+            Some("text/html"),
+        ),
+        ReferenceCase::new(
+            SOURCE,
+            ContentType,
+            &["text/html", "application/json"],
+            "*/*",
+            Some("text/html"),
+        ),
+        ReferenceCase::new(
+            SOURCE,
+            ContentType,
+            &["application/json", "text/html"],
+            "application/json;q=0.5, text/html",
+            Some("text/html"),
+        ),
+        ReferenceCase::new(
+            SOURCE,
+            ContentType,
+            &["text/plain", "text/html"],
+            "text/*, text/plain;q=0",
+            Some("text/html"),
+        ),
+        ReferenceCase::new(SOURCE, ContentType, &["text/html"], "text/html;q=0", None),
+        ReferenceCase::new(
+            SOURCE,
+            ContentType,
+            &["text/html;level=1", "text/html"],
+            "text/html;level=1",
+            Some("text/html;level=1"),
+        ),
+        ReferenceCase::new(SOURCE, Language, &["en-US"], "en", Some("en-US")),
+        ReferenceCase::new(
+            SOURCE,
+            Language,
+            &["en-US", "fr-FR"],
+            "fr;q=0.8, en;q=0.5",
+            Some("fr-FR"),
+        ),
+        ReferenceCase::new(SOURCE, Language, &["en-US"], "fr", None),
+        ReferenceCase::new(SOURCE, Encoding, &["gzip", "deflate"], "*", Some("gzip")),
+        ReferenceCase::new(
+            SOURCE,
+            Encoding,
+            &["compress", "gzip"],
+            "gzip, compress;q=0",
+            Some("gzip"),
+        ),
+        ReferenceCase::new(SOURCE, Encoding, &["identity"], "gzip", Some("identity")),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, negotiator_npm_cases, ReferenceCase};
+    use crate::{Error, NegotiationKind};
+
+    #[test]
+    fn report() {
+        let cases = [
+            ReferenceCase::new(
+                "local",
+                NegotiationKind::ContentType,
+                &["application/json", "text/html"],
+                "text/html",
+                Some("text/html"),
+            ),
+            ReferenceCase::new("local", NegotiationKind::Language, &["en-US"], "en", None),
+            ReferenceCase::new(
+                "local",
+                NegotiationKind::Encoding,
+                &["gzip"],
+                "gzip;q=x",
+                Some("gzip"),
+            ),
+        ];
+        let report = compare(&cases);
+        assert_eq!(report.total, 3);
+        assert!(!report.is_success());
+        assert_eq!(report.mismatches.len(), 2);
+        assert_eq!(report.mismatches[0].case, cases[1]);
+        assert_eq!(report.mismatches[0].actual, Ok(Some("en-US".to_owned())));
+        assert!(matches!(
+            report.mismatches[1].actual,
+            Err(Error::InvalidQuality { .. })
+        ));
+    }
+
+    #[test]
+    fn embedded() {
+        let cases = negotiator_npm_cases();
+        let report = compare(&cases);
+        assert_eq!(report.total, cases.len());
+        assert!(report.is_success(), "{:?}", report.mismatches);
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+
+use serde::{ser::SerializeSeq, Deserialize, Serialize, Serializer};
+
+use crate::{
+    DuplicatePolicy, Error, Limits, NegotiationType, Negotiator, ParamMatching, Scoring, TieBreak,
+    UnknownParams, Weighting, WildcardPolicy,
+};
+
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct NegotiatorConfig {
+    pub supported: Vec<String>,
+    pub default: Option<String>,
+    pub aliases: BTreeMap<String, String>,
+    pub fallbacks: Vec<Vec<String>>,
+    pub strict: bool,
+    pub lenient: bool,
+    pub strict_quality: bool,
+    pub browser_quirks: bool,
+    pub suffix_matching: bool,
+    pub wildcard_supported: bool,
+    pub decode_params: bool,
+    pub param_matching: ParamMatching,
+    pub unknown_params: UnknownParams,
+    pub wildcard: WildcardPolicy,
+    pub tie_break: TieBreak,
+    pub scoring: Scoring,
+    pub weighting: Weighting,
+    pub duplicates: DuplicatePolicy,
+    pub limits: Limits,
+}
+
+impl<N> TryFrom<NegotiatorConfig> for Negotiator<N, String>
+where
+    N: NegotiationType,
+    N::Parsed: PartialEq,
+{
+    type Error = Error;
+
+    fn try_from(config: NegotiatorConfig) -> Result<Self, Self::Error> {
+        let mut builder = Negotiator::builder()
+            .extend(config.supported)
+            .strict(config.strict)
+            .lenient(config.lenient)
+            .strict_quality(config.strict_quality)
+            .browser_quirks(config.browser_quirks)
+            .suffix_matching(config.suffix_matching)
+            .wildcard_supported(config.wildcard_supported)
+            .decode_params(config.decode_params)
+            .param_matching(config.param_matching)
+            .unknown_params(config.unknown_params)
+            .wildcard_policy(config.wildcard)
+            .tie_break(config.tie_break)
+            .scoring(config.scoring)
+            .weighting(config.weighting)
+            .duplicates(config.duplicates)
+            .limits(config.limits)
+            .aliases(config.aliases.iter().map(|(a, t)| (a.as_str(), t.as_str())));
+        for chain in &config.fallbacks {
+            builder = builder.fallback(chain.iter().map(String::as_str));
+        }
+        if let Some(default) = config.default {
+            builder = builder.default_value(default);
+        }
+        builder.build()
+    }
+}
+
+impl<N: NegotiationType, T: Serialize> Serialize for Negotiator<N, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for value in self.supported() {
+            seq.serialize_element(value)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NegotiatorConfig;
+    use crate::{Error, LanguageNegotiation, Negotiator, TieBreak, WildcardPolicy};
+
+    #[test]
+    fn deserialize() {
+        let config = serde_json::from_str::<NegotiatorConfig>(
+            r#"{
+                "supported": ["en-US", "fr-FR"],
+                "default": "fr-FR",
+                "strict": true,
+                "wildcard": "reject",
+                "tie-break": "server-order"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.wildcard, WildcardPolicy::Reject);
+        assert!(matches!(config.tie_break, TieBreak::ServerOrder));
+
+        let negotiator = Negotiator::<LanguageNegotiation, _>::try_from(config).unwrap();
+        assert_eq!(
+            negotiator.default_value().map(String::as_str),
+            Some("fr-FR")
+        );
+        assert_eq!(
+            negotiator.negotiate("de-DE").unwrap_err(),
+            Error::NotAcceptable
+        );
+        assert_eq!(
+            serde_json::to_string(&negotiator).unwrap(),
+            r#"["en-US","fr-FR"]"#
+        );
+
+        let config =
+            serde_json::from_str::<NegotiatorConfig>(r#"{"supported": ["en;q=1"]}"#).unwrap();
+        assert_eq!(
+            Negotiator::<LanguageNegotiation, _>::try_from(config).unwrap_err(),
+            Error::ParamsNotAllowed
+        );
+
+        let config = serde_json::from_str::<NegotiatorConfig>(
+            r#"{"supported": ["en-US", "en-US"], "duplicates": "error"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Negotiator::<LanguageNegotiation, _>::try_from(config).unwrap_err(),
+            Error::DuplicateSupported
+        );
+
+        assert!(serde_json::from_str::<NegotiatorConfig>(r#"{"tie-break": "custom"}"#).is_err());
+    }
+}
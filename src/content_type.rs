@@ -1,51 +1,228 @@
-use std::{borrow::Borrow, collections::BTreeMap};
+use std::{borrow::Cow, collections::BTreeMap};
 
 use crate::{
-    extract_quality, match_first, AsNegotiationStr, Error, MaybeWildcard, NegotiationType,
+    media_types::StaticMediaType,
+    params::{split_unquoted, unquote},
+    AsNegotiationStr, EncodingNegotiation, Entry, Error, HeaderElements, HeaderParams,
+    MatchOptions, MaybeWildcard, MediaType, NegotiationKind, NegotiationType, Negotiator,
+    ParamMatching, Quality,
 };
 
 #[derive(Copy, Clone, Debug)]
 pub struct ContentTypeNegotiation;
 
 impl NegotiationType for ContentTypeNegotiation {
-    type Parsed = (String, String, BTreeMap<String, String>);
+    type Parsed = MediaType;
+    // Type, subtype, media type parameters and accept extensions (the parameters after `q`).
+    type Range<'a> = (
+        MaybeWildcard<&'a str>,
+        MaybeWildcard<&'a str>,
+        HeaderParams<'a>,
+        HeaderParams<'a>,
+    );
+
+    const KIND: NegotiationKind = NegotiationKind::ContentType;
 
+    // Types, subtypes and parameter names are case-insensitive, they are lowercased here and
+    // compared ignoring case against header ranges.
     fn parse_elem<M: AsNegotiationStr>(raw: &M) -> Result<Self::Parsed, Error> {
-        parse_mime(raw.as_str(), false)
-    }
-
-    fn parse_and_negotiate_header<'a, T>(
-        supported: &'a [(Self::Parsed, T)],
-        header: &str,
-    ) -> Result<Option<&'a T>, Error> {
-        let mimes = parse_and_sort_header(header)?;
-        Ok(match_first(
-            supported,
-            mimes.iter().map(|(ct, _q)| ct),
-            |s, h| {
-                h.0.matches(&s.0)
-                    && h.1.matches(&s.1)
-                    && s.2
-                        .iter()
-                        .map(|(k, v)| (k.as_str(), v.as_str()))
-                        .eq(h.2.iter().map(|(k, v)| (*k, *v)))
-            },
+        let (main, sub, params) = parse_mime::<&str>(raw.as_str(), false)?;
+        Ok(MediaType::new(main, sub, params))
+    }
+
+    // Wildcard supported types are stored as `*`, which matches any header type.
+    fn parse_wildcard_elem<M: AsNegotiationStr>(raw: &M) -> Result<Self::Parsed, Error> {
+        let (main, sub, params) = parse_mime::<&str>(raw.as_str(), true)?;
+        if params.contains("q") {
+            return Err(Error::QualityNotAllowed);
+        }
+        Ok(MediaType::new(main, sub, params))
+    }
+
+    fn parse_header_elem(input: &str) -> Result<(Self::Range<'_>, Quality), Error> {
+        parse_range(input, false)
+    }
+
+    fn parse_quirky_header_elem(input: &str) -> Result<(Self::Range<'_>, Quality), Error> {
+        parse_range(input, true)
+    }
+
+    fn empty_header<'a>() -> Option<(Self::Range<'a>, Quality)> {
+        Some((
+            (
+                MaybeWildcard::Wildcard,
+                MaybeWildcard::Wildcard,
+                HeaderParams::default(),
+                HeaderParams::default(),
+            ),
+            Quality::ONE,
         ))
     }
 
-    #[cfg(feature = "axum")]
+    fn range_key(range: &Self::Range<'_>) -> Option<String> {
+        match (&range.0, &range.1) {
+            (MaybeWildcard::Specific(main), MaybeWildcard::Specific(sub)) if range.2.is_empty() => {
+                Some(format!("{main}/{sub}").to_ascii_lowercase())
+            }
+            _ => None,
+        }
+    }
+
+    // Supported values with parameters may match ranges with other ones, and wildcard ones any
+    // type.
+    fn supported_key(supported: &Self::Parsed) -> Option<String> {
+        (supported.params().is_empty() && supported.main() != "*" && supported.sub() != "*")
+            .then(|| supported.to_string())
+    }
+
+    fn precedence(range: &Self::Range<'_>) -> (u8, usize) {
+        (mime_precision_score(&range.0, &range.1), range.2.len())
+    }
+
+    fn specificity(supported: &Self::Parsed) -> usize {
+        supported.params().len()
+    }
+
+    fn matches(supported: &Self::Parsed, range: &Self::Range<'_>) -> bool {
+        matches_params(supported, range, range.2.iter(), ParamMatching::Exact)
+    }
+
+    fn matches_with(
+        supported: &Self::Parsed,
+        range: &Self::Range<'_>,
+        options: MatchOptions,
+    ) -> bool {
+        if !options.decode_params {
+            return matches_params(supported, range, range.2.iter(), options.params);
+        }
+        match range
+            .2
+            .iter()
+            .map(|(k, v)| decode_param(k, v))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(params) => matches_params(
+                supported,
+                range,
+                params.iter().map(|(k, v)| (*k, v.as_ref())),
+                options.params,
+            ),
+            Err(_) => false,
+        }
+    }
+
+    // A subtype matches its RFC 6839 structured syntax suffix either way, e.g. `application/json`
+    // and `application/problem+json`.
+    fn matches_suffix(
+        supported: &Self::Parsed,
+        range: &Self::Range<'_>,
+        options: MatchOptions,
+    ) -> bool {
+        let MaybeWildcard::Specific(sub) = range.1 else {
+            return false;
+        };
+        let related = suffix(sub).is_some_and(|s| s.eq_ignore_ascii_case(supported.sub()))
+            || supported
+                .suffix()
+                .is_some_and(|s| s.eq_ignore_ascii_case(sub));
+        related
+            && part_matches(&range.0, supported.main())
+            && params_match(supported, &range.1, range.2.iter(), options.params)
+    }
+
+    fn has_param(supported: &Self::Parsed, name: &str) -> bool {
+        supported
+            .params()
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case(name))
+    }
+
+    fn retain_params<'a>(range: &Self::Range<'a>, keep: &dyn Fn(&str) -> bool) -> Self::Range<'a> {
+        (range.0, range.1, range.2.retain(keep), range.3)
+    }
+
+    fn is_wildcard(range: &Self::Range<'_>) -> bool {
+        range.1 == MaybeWildcard::Wildcard
+    }
+
+    fn format_range(range: &Self::Range<'_>) -> String {
+        range
+            .2
+            .iter()
+            .fold(format!("{}/{}", range.0, range.1), |mime, (k, v)| {
+                format!("{mime};{k}={v}")
+            })
+    }
+
+    // Parameter values are case-sensitive, except for charsets. Accept extensions are dropped.
+    fn format_canonical(range: &Self::Range<'_>) -> String {
+        range
+            .2
+            .iter()
+            .map(|(k, v)| match k.to_ascii_lowercase() {
+                k if k == "charset" => (k, unquote(v).to_ascii_lowercase()),
+                k => (k, (*v).to_owned()),
+            })
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .fold(
+                format!("{}/{}", range.0, range.1).to_ascii_lowercase(),
+                |mime, (k, v)| format!("{mime};{k}={v}"),
+            )
+    }
+
+    #[cfg(feature = "http")]
     fn associated_header() -> http::header::HeaderName {
         http::header::ACCEPT
     }
 }
 
-fn parse_mime<'a, W, T>(mime: &'a str, from_header: bool) -> Result<(W, W, BTreeMap<T, T>), Error>
+fn parse_range(
+    input: &str,
+    quirks: bool,
+) -> Result<
+    (
+        <ContentTypeNegotiation as NegotiationType>::Range<'_>,
+        Quality,
+    ),
+    Error,
+> {
+    let (media, q, extensions) = split_quality(input.trim());
+    let (left, params) = media.split_at(media.find(';').unwrap_or(media.len()));
+    // With browser quirks, a bare `*` stands for `*/*`, e.g. `*; q=.2` sent by older HTTP clients.
+    let (main, sub, params) = if quirks && left.trim() == "*" {
+        (
+            MaybeWildcard::Wildcard,
+            MaybeWildcard::Wildcard,
+            HeaderParams::parse(params)?,
+        )
+    } else if quirks {
+        parse_quirky_mime(media)?
+    } else {
+        parse_mime::<MaybeWildcard<&str>>(media, true)?
+    };
+    let q = q.map_or(Ok(Quality::ONE), Quality::parse_lenient)?;
+    Ok(((main, sub, params, HeaderParams::extensions(extensions)), q))
+}
+
+// Some browsers send spaces around the `/`.
+fn parse_quirky_mime(
+    media: &str,
+) -> Result<(MaybeWildcard<&str>, MaybeWildcard<&str>, HeaderParams<'_>), Error> {
+    let (main, sub, params) = parse_mime::<&str>(media, true)?;
+    let (main, sub) = (main.trim(), sub.trim());
+    if main == "*" && sub != "*" {
+        return Err(Error::InvalidWildcard);
+    }
+    Ok((main.into(), sub.into(), params))
+}
+
+fn parse_mime<'a, W>(mime: &'a str, from_header: bool) -> Result<(W, W, HeaderParams<'a>), Error>
 where
     W: From<&'a str>,
-    T: From<&'a str> + Ord + Borrow<str>,
 {
-    let mut parts = mime.split(';');
-    let left = parts.next().ok_or(Error::InvalidHeader)?.trim();
+    let (left, params) = mime.split_at(mime.find(';').unwrap_or(mime.len()));
+    let left = left.trim();
 
     let (main, sub) = left.split_once('/').ok_or(Error::MissingSeparator('/'))?;
     if sub.contains('/') {
@@ -59,57 +236,274 @@ where
         return Err(Error::InvalidWildcard);
     }
 
-    let params = parts
-        .map(|param| {
-            let (k, v) = param.trim().split_once('=').ok_or(Error::InvalidHeader)?;
-            Ok((k.into(), v.into()))
-        })
-        .collect::<Result<BTreeMap<_, _>, _>>()?;
-    if !from_header && params.contains_key("q") {
+    let params = HeaderParams::parse(params)?;
+    if !from_header && params.contains("q") {
         return Err(Error::QualityNotAllowed);
     }
 
     Ok((main.into(), sub.into(), params))
 }
 
-#[allow(clippy::type_complexity)]
-fn parse_and_sort_header(
-    header: &str,
-) -> Result<
-    Vec<(
-        (
-            MaybeWildcard<&str>,
-            MaybeWildcard<&str>,
-            BTreeMap<&str, &str>,
-        ),
-        f32,
-    )>,
-    Error,
-> {
-    let mut mimes = header
-        .split(',')
-        .map(|m| {
-            let (main, sub, mut params) = parse_mime::<MaybeWildcard<&str>, &str>(m.trim(), true)?;
-            let q = extract_quality(&mut params)?;
-            Ok(((main, sub, params), q))
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-    mimes.sort_by(
-        |((main_lhs, sub_lhs, params_lhs), q_lhs), ((main_rhs, sub_rhs, params_rhs), q_rhs)| {
-            q_lhs
-                .total_cmp(q_rhs)
-                .then_with(|| {
-                    mime_precision_score(main_lhs, sub_lhs)
-                        .cmp(&mime_precision_score(main_rhs, sub_rhs))
-                })
-                .then_with(|| params_lhs.len().cmp(&params_rhs.len()))
-                .reverse()
-        },
-    );
-    Ok(mimes)
+impl Negotiator<ContentTypeNegotiation, StaticMediaType> {
+    /// Builds a negotiator from [`media_types`](crate::media_types) constants, which cannot fail.
+    pub fn from_static<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = StaticMediaType>,
+    {
+        Self::new(iter).expect("static media types are valid")
+    }
 }
 
-fn mime_precision_score(main: &MaybeWildcard<&str>, sub: &MaybeWildcard<&str>) -> u8 {
+impl<T> Negotiator<ContentTypeNegotiation, T> {
+    /// Negotiates a content type along with the charset to serve it in: the one of the selected
+    /// value, otherwise the one asked by the matching range. When an `Accept-Charset` header is
+    /// given, a charset it does not accept is dropped.
+    #[allow(clippy::type_complexity)]
+    pub fn negotiate_charset(
+        &self,
+        accept: &str,
+        accept_charset: Option<&str>,
+    ) -> Result<Option<(&T, Option<String>)>, Error> {
+        let ranges = self.parse_header(accept)?;
+        let Some(selected) = self.select(&ranges)? else {
+            return Ok(None);
+        };
+        let charset = selected
+            .parsed
+            .and_then(|parsed| parsed.param("charset"))
+            .or_else(|| ranges[selected.range?.0].0 .2.get("charset"))
+            .map(unquote)
+            .filter(|charset| accept_charset.is_none_or(|h| charset_accepted(h, charset)))
+            .map(|charset| charset.to_ascii_lowercase());
+        Ok(Some((selected.value, charset)))
+    }
+
+    /// Negotiates a versioned media type, e.g. `application/vnd.api+json;version=2`, comparing
+    /// the `param` version numerically: the highest supported version not above the requested one
+    /// is selected, or the highest one when the range asks for none. Other parameters match as
+    /// usual and supported values without a version only match ranges without one. Qualities,
+    /// weights, availability and tie breaking apply as with [`Negotiator::negotiate`].
+    pub fn negotiate_version(&self, header: &str, param: &str) -> Result<Option<&T>, Error> {
+        let version = |v: &str| v.trim_matches('"').parse::<u64>().ok();
+        let ranges = self.parse_header(header)?;
+        let ranges = self.known_ranges(&ranges);
+        // The version asked by each range, a range with an invalid one matching nothing.
+        let requested = ranges
+            .iter()
+            .map(|(range, _)| match range.2.get(param) {
+                Some(v) => version(v).map(Some),
+                None => Some(None),
+            })
+            .collect::<Vec<_>>();
+        let unversioned = ranges
+            .iter()
+            .map(|(range, _)| (range.0, range.1, range.2.without(param), range.3))
+            .collect::<Vec<_>>();
+        // The version an entry serves to the range at `i`, if it matches it.
+        let served = |entry: &Entry<MediaType, T>, i: usize| {
+            let requested = requested[i]?;
+            let supported = match (entry.parsed.param(param).map(version), requested) {
+                (Some(Some(v)), Some(requested)) if v <= requested => Some(v),
+                (Some(Some(v)), None) => Some(v),
+                (None, None) => None,
+                _ => return None,
+            };
+            let parsed = entry.parsed.without_param(param);
+            ContentTypeNegotiation::matches_with(&parsed, &unversioned[i], self.options.matching)
+                .then_some(supported)
+        };
+        // Each range only matches the highest version served among the selectable entries.
+        let now = self.now();
+        let highest = (0..ranges.len())
+            .map(|i| {
+                self.supported
+                    .iter()
+                    .filter(|entry| entry.is_selectable(now))
+                    .filter_map(|entry| served(entry, i))
+                    .max()
+            })
+            .collect::<Vec<_>>();
+        let selected = self.best_by(&ranges, None, |entry, i| {
+            served(entry, i).is_some_and(|v| Some(v) == highest[i])
+        });
+        match selected {
+            Some(selected) => Ok(Some(&selected.entry.value)),
+            None if self.options.strict => Err(Error::NotAcceptable),
+            None => Ok(None),
+        }
+    }
+}
+
+// The most specific `Accept-Charset` element decides, a charset not listed being unacceptable.
+fn charset_accepted(header: &str, charset: &str) -> bool {
+    let mut wildcard = None;
+    for (range, q) in HeaderElements::<EncodingNegotiation>::new(header).flatten() {
+        match range {
+            MaybeWildcard::Specific(c) if c.eq_ignore_ascii_case(charset) => {
+                return q > Quality::ZERO
+            }
+            MaybeWildcard::Specific(_) => {}
+            MaybeWildcard::Wildcard => wildcard = wildcard.or(Some(q)),
+        }
+    }
+    wildcard.is_some_and(|q| q > Quality::ZERO)
+}
+
+// Splits a media range at its `q` parameter, returning the media type with its parameters, the
+// quality value and the accept extensions following it.
+fn split_quality(input: &str) -> (&str, Option<&str>, &str) {
+    let mut offset = 0;
+    for (i, param) in split_unquoted(input, ';').enumerate() {
+        if i > 0 {
+            if let Some((k, v)) = param.split_once('=') {
+                if k.trim().eq_ignore_ascii_case("q") {
+                    return (
+                        &input[..offset - 1],
+                        Some(v.trim()),
+                        &input[offset + param.len()..],
+                    );
+                }
+            }
+        }
+        offset += param.len() + 1;
+    }
+    (input, None, "")
+}
+
+fn matches_params<'p>(
+    supported: &MediaType,
+    range: &<ContentTypeNegotiation as NegotiationType>::Range<'_>,
+    params: impl Iterator<Item = (&'p str, &'p str)> + Clone,
+    policy: ParamMatching,
+) -> bool {
+    part_matches(&range.0, supported.main())
+        && part_matches(&range.1, supported.sub())
+        && params_match(supported, &range.1, params, policy)
+}
+
+// A charset on a single side of a specific range does not prevent a match, the other side
+// accepting or serving any charset, and neither does a supported RFC 6906 profile the client did
+// not ask for.
+fn params_match<'p>(
+    supported: &MediaType,
+    sub: &MaybeWildcard<&str>,
+    params: impl Iterator<Item = (&'p str, &'p str)> + Clone,
+    policy: ParamMatching,
+) -> bool {
+    let in_range = |name: &str| params.clone().any(|(k, _)| k.eq_ignore_ascii_case(name));
+    let any_charset = matches!(sub, MaybeWildcard::Specific(_))
+        && in_range("charset") != supported.params().contains_key("charset");
+    let any_profile = !in_range("profile");
+    let optional = |name: &str| {
+        (any_charset && name.eq_ignore_ascii_case("charset"))
+            || (any_profile && name.eq_ignore_ascii_case("profile"))
+    };
+    let mut params = params.clone().filter(|(k, _)| !optional(k));
+    let supported_params = supported.params().iter().filter(|(k, _)| !optional(k));
+    let param_eq = |(k, v): (&str, &str), (sk, sv): (&String, &String)| {
+        sk.eq_ignore_ascii_case(k) && value_matches(sk, sv, v)
+    };
+    let has_param = |param: (&str, &str)| supported_params.clone().any(|s| param_eq(param, s));
+    match (policy, sub) {
+        (ParamMatching::Ignore, _) => true,
+        (ParamMatching::SupportedSubset, _) => supported_params
+            .clone()
+            .all(|s| params.clone().any(|param| param_eq(param, s))),
+        (ParamMatching::Compatible, _) => params.all(|(k, v)| {
+            supported_params
+                .clone()
+                .filter(|(sk, _)| sk.eq_ignore_ascii_case(k))
+                .all(|s| param_eq((k, v), s))
+        }),
+        // Parameters on a wildcard range constrain the matching types without
+        // requiring them to carry no other parameter.
+        (ParamMatching::Exact, MaybeWildcard::Wildcard) => params.all(has_param),
+        (ParamMatching::Exact, MaybeWildcard::Specific(_)) => {
+            params.clone().count() == supported_params.clone().count() && params.all(has_param)
+        }
+    }
+}
+
+// Requested values are unquoted before comparing, supported values are already stored
+// unquoted. Charset names are case-insensitive and a supported profile list must contain every
+// profile URI asked for.
+fn value_matches(name: &str, supported: &str, requested: &str) -> bool {
+    let requested = unquote(requested);
+    let requested = requested.as_ref();
+    match name {
+        "charset" => supported.eq_ignore_ascii_case(requested),
+        "profile" => requested
+            .split_ascii_whitespace()
+            .all(|uri| supported.split_ascii_whitespace().any(|s| s == uri)),
+        _ => supported == requested,
+    }
+}
+
+fn suffix(subtype: &str) -> Option<&str> {
+    subtype.rsplit_once('+').map(|(_, suffix)| suffix)
+}
+
+fn part_matches(range: &MaybeWildcard<&str>, supported: &str) -> bool {
+    match range {
+        MaybeWildcard::Specific(part) => supported == "*" || part.eq_ignore_ascii_case(supported),
+        MaybeWildcard::Wildcard => true,
+    }
+}
+
+/// Decodes a header parameter, percent-decoding its value as UTF-8, or following RFC 8187 when the
+/// name ends with `*` (e.g. `title*=utf-8'en'%E2%82%AC`), in which case the `*` is stripped.
+pub fn decode_param<'a>(name: &'a str, value: &'a str) -> Result<(&'a str, Cow<'a, str>), Error> {
+    let Some(name) = name.strip_suffix('*') else {
+        return Ok((name, percent_decode_utf8(value)?));
+    };
+    let mut parts = value.splitn(3, '\'');
+    let (Some(charset), Some(_language), Some(encoded)) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(Error::InvalidHeader);
+    };
+    let value = if charset.eq_ignore_ascii_case("utf-8") {
+        percent_decode_utf8(encoded)?
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        Cow::Owned(
+            percent_decode(encoded)?
+                .into_iter()
+                .map(char::from)
+                .collect(),
+        )
+    } else {
+        return Err(Error::InvalidHeader);
+    };
+    Ok((name, value))
+}
+
+fn percent_decode_utf8(value: &str) -> Result<Cow<'_, str>, Error> {
+    if !value.contains('%') {
+        return Ok(Cow::Borrowed(value));
+    }
+    String::from_utf8(percent_decode(value)?)
+        .map(Cow::Owned)
+        .map_err(|_| Error::InvalidHeader)
+}
+
+fn percent_decode(value: &str) -> Result<Vec<u8>, Error> {
+    let mut bytes = value.bytes();
+    let mut decoded = Vec::with_capacity(value.len());
+    while let Some(b) = bytes.next() {
+        if b != b'%' {
+            decoded.push(b);
+            continue;
+        }
+        let hex = [bytes.next(), bytes.next()];
+        let [Some(high), Some(low)] = hex.map(|h| h.and_then(|h| (h as char).to_digit(16))) else {
+            return Err(Error::InvalidHeader);
+        };
+        decoded.push((high * 16 + low) as u8);
+    }
+    Ok(decoded)
+}
+
+pub(crate) fn mime_precision_score(main: &MaybeWildcard<&str>, sub: &MaybeWildcard<&str>) -> u8 {
     match (main, sub) {
         (MaybeWildcard::Wildcard, MaybeWildcard::Wildcard) => 0,
         (_, MaybeWildcard::Wildcard) => 1,
@@ -119,19 +513,23 @@ fn mime_precision_score(main: &MaybeWildcard<&str>, sub: &MaybeWildcard<&str>) -
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use std::{borrow::Cow, time::SystemTime};
 
-    use super::{parse_and_sort_header, parse_mime, ContentTypeNegotiation};
-    use crate::{Error, MaybeWildcard, Negotiator};
+    use super::{decode_param, parse_mime, ContentTypeNegotiation};
+    use crate::{
+        parse_and_sort_header, Availability, DuplicatePolicy, Entry, Error, HeaderElements,
+        HeaderParams, Limits, MaybeWildcard, MediaType, NegotiationType, Negotiator, ParsedHeader,
+        Quality, Scoring, TieBreak, Weighting, WildcardPolicy,
+    };
 
     #[test]
     fn new() {
         assert_eq!(
-            Negotiator::<ContentTypeNegotiation, _>::new(["text/plain"])
+            *Negotiator::<ContentTypeNegotiation, _>::new(["text/plain"])
                 .unwrap()
                 .supported,
-            vec![(
-                ("text".to_owned(), "plain".to_owned(), BTreeMap::default()),
+            vec![Entry::new(
+                MediaType::new("text", "plain", HeaderParams::default()),
                 "text/plain"
             )]
         );
@@ -146,20 +544,20 @@ mod tests {
     fn parse() {
         // Basic.
         assert_eq!(
-            parse_mime::<_, &str>("text/plain", false).unwrap(),
-            ("text", "plain", BTreeMap::default()),
+            parse_mime::<&str>("text/plain", false).unwrap(),
+            ("text", "plain", HeaderParams::default()),
         );
 
         // With one param.
         assert_eq!(
             parse_mime("text/html;level=1", false).unwrap(),
-            ("text", "html", BTreeMap::from([("level", "1")]),)
+            ("text", "html", HeaderParams::parse(";level=1").unwrap(),)
         );
 
         // Param with space.
         assert_eq!(
             parse_mime("text/html; level=1", false).unwrap(),
-            ("text", "html", BTreeMap::from([("level", "1")]),)
+            ("text", "html", HeaderParams::parse(";level=1").unwrap(),)
         );
 
         // Multiple params.
@@ -168,29 +566,29 @@ mod tests {
             (
                 "text",
                 "html",
-                BTreeMap::from([("level", "1"), ("origin", "EU")]),
+                HeaderParams::parse(";level=1;origin=EU").unwrap(),
             )
         );
 
         assert_eq!(
-            parse_mime::<&str, &str>("text/plain;q=1", false).unwrap_err(),
+            parse_mime::<&str>("text/plain;q=1", false).unwrap_err(),
             Error::QualityNotAllowed,
         );
 
         assert_eq!(
-            parse_mime::<&str, &str>("*/plain", true).unwrap_err(),
+            parse_mime::<&str>("*/plain", true).unwrap_err(),
             Error::InvalidWildcard
         );
 
         assert_eq!(
-            parse_mime::<&str, &str>("text/*", false).unwrap_err(),
+            parse_mime::<&str>("text/*", false).unwrap_err(),
             Error::InvalidWildcard
         );
 
-        assert!(parse_mime::<&str, &str>("text/*", true).is_ok());
+        assert!(parse_mime::<&str>("text/*", true).is_ok());
 
         assert_eq!(
-            parse_mime::<&str, &str>("text/plain/extra", true).unwrap_err(),
+            parse_mime::<&str>("text/plain/extra", true).unwrap_err(),
             Error::TooManyParts
         );
     }
@@ -198,107 +596,136 @@ mod tests {
     #[test]
     fn parse_sort() {
         assert_eq!(
-            parse_and_sort_header("text/*, text/plain, text/plain;format=flowed, */*").unwrap(),
+            parse_and_sort_header(
+                HeaderElements::<ContentTypeNegotiation>::new(
+                    "text/*, text/plain, text/plain;format=flowed, */*"
+                ),
+                None
+            )
+            .unwrap(),
             vec![
                 (
                     (
                         MaybeWildcard::Specific("text"),
-                        MaybeWildcard::Specific("plain").into(),
-                        BTreeMap::from([("format", "flowed")])
+                        MaybeWildcard::Specific("plain"),
+                        HeaderParams::parse(";format=flowed").unwrap(),
+                        HeaderParams::default()
                     ),
-                    1.
+                    Quality::ONE
                 ),
                 (
                     (
                         MaybeWildcard::Specific("text"),
                         MaybeWildcard::Specific("plain"),
-                        BTreeMap::default()
+                        HeaderParams::default(),
+                        HeaderParams::default()
                     ),
-                    1.
+                    Quality::ONE
                 ),
                 (
                     (
                         MaybeWildcard::Specific("text"),
                         MaybeWildcard::Wildcard,
-                        BTreeMap::default()
+                        HeaderParams::default(),
+                        HeaderParams::default()
                     ),
-                    1.
+                    Quality::ONE
                 ),
                 (
                     (
                         MaybeWildcard::Wildcard,
                         MaybeWildcard::Wildcard,
-                        BTreeMap::default()
+                        HeaderParams::default(),
+                        HeaderParams::default()
                     ),
-                    1.
+                    Quality::ONE
                 ),
             ]
         );
 
         assert_eq!(
-            parse_and_sort_header("text/*, text/plain, text/plain;format=flowed, */*").unwrap(),
+            parse_and_sort_header(
+                HeaderElements::<ContentTypeNegotiation>::new(
+                    "text/*, text/plain, text/plain;format=flowed, */*"
+                ),
+                None
+            )
+            .unwrap(),
             vec![
                 (
                     (
                         MaybeWildcard::Specific("text"),
                         MaybeWildcard::Specific("plain"),
-                        BTreeMap::from([("format", "flowed")])
+                        HeaderParams::parse(";format=flowed").unwrap(),
+                        HeaderParams::default()
                     ),
-                    1.
+                    Quality::ONE
                 ),
                 (
                     (
                         MaybeWildcard::Specific("text"),
                         MaybeWildcard::Specific("plain"),
-                        BTreeMap::default()
+                        HeaderParams::default(),
+                        HeaderParams::default()
                     ),
-                    1.
+                    Quality::ONE
                 ),
                 (
                     (
                         MaybeWildcard::Specific("text"),
                         MaybeWildcard::Wildcard,
-                        BTreeMap::default()
+                        HeaderParams::default(),
+                        HeaderParams::default()
                     ),
-                    1.
+                    Quality::ONE
                 ),
                 (
                     (
                         MaybeWildcard::Wildcard,
                         MaybeWildcard::Wildcard,
-                        BTreeMap::default()
+                        HeaderParams::default(),
+                        HeaderParams::default()
                     ),
-                    1.
+                    Quality::ONE
                 ),
             ]
         );
 
         assert_eq!(
-            parse_and_sort_header("text/plain;q=0.2,text/not-plain;q=0.4,text/hybrid").unwrap(),
+            parse_and_sort_header(
+                HeaderElements::<ContentTypeNegotiation>::new(
+                    "text/plain;q=0.2,text/not-plain;q=0.4,text/hybrid"
+                ),
+                None
+            )
+            .unwrap(),
             vec![
                 (
                     (
                         MaybeWildcard::Specific("text"),
                         MaybeWildcard::Specific("hybrid"),
-                        BTreeMap::default()
+                        HeaderParams::default(),
+                        HeaderParams::default()
                     ),
-                    1.
+                    Quality::ONE
                 ),
                 (
                     (
                         MaybeWildcard::Specific("text"),
                         MaybeWildcard::Specific("not-plain"),
-                        BTreeMap::default()
+                        HeaderParams::default(),
+                        HeaderParams::default()
                     ),
-                    0.4
+                    Quality::from_thousandths(400).unwrap()
                 ),
                 (
                     (
                         MaybeWildcard::Specific("text"),
                         MaybeWildcard::Specific("plain"),
-                        BTreeMap::default()
+                        HeaderParams::default(),
+                        HeaderParams::default()
                     ),
-                    0.2
+                    Quality::from_thousandths(200).unwrap()
                 ),
             ]
         );
@@ -370,4 +797,971 @@ mod tests {
             Some(&"application/json")
         );
     }
+
+    #[test]
+    fn parse_sort_duplicates() {
+        assert_eq!(
+            parse_and_sort_header(
+                HeaderElements::<ContentTypeNegotiation>::new(
+                    "text/html;q=0.2, text/plain;q=0.5, text/html, text/plain;q=0"
+                ),
+                None
+            )
+            .unwrap()
+            .into_iter()
+            .map(|(r, q)| (ContentTypeNegotiation::format_range(&r), q))
+            .collect::<Vec<_>>(),
+            vec![
+                ("text/html".to_owned(), Quality::ONE),
+                (
+                    "text/plain".to_owned(),
+                    Quality::from_thousandths(500).unwrap()
+                ),
+            ]
+        );
+
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["text/plain", "text/html"]).unwrap();
+        assert_eq!(
+            negotiator
+                .negotiate("text/html;q=0.2, text/plain;q=0.5, text/html")
+                .unwrap(),
+            Some(&"text/html")
+        );
+    }
+
+    #[test]
+    fn negotiate_empty() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["application/json", "text/html"])
+                .unwrap();
+        assert_eq!(negotiator.negotiate("").unwrap(), Some(&"application/json"));
+        assert_eq!(
+            negotiator.negotiate_or_first(Some(" ")),
+            Ok(&"application/json")
+        );
+        assert_eq!(ContentTypeNegotiation::validate_header(""), Ok(()));
+    }
+
+    #[test]
+    fn negotiate_bare_wildcard() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["text/html", "application/json"])
+                .unwrap();
+        assert_eq!(
+            negotiator.negotiate("*").unwrap_err(),
+            Error::MissingSeparator('/')
+        );
+
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .extend(["text/html", "application/json"])
+            .browser_quirks(true)
+            .build()
+            .unwrap();
+        assert_eq!(negotiator.negotiate("*").unwrap(), Some(&"text/html"));
+        assert_eq!(
+            negotiator
+                .negotiate_with_quality("application/json;q=0.5, *; q=.2")
+                .unwrap(),
+            Some((&"application/json", Quality::from_thousandths(500).unwrap()))
+        );
+        assert_eq!(
+            negotiator
+                .negotiate_with_quality("*;q=0.1, text/html;q=0")
+                .unwrap(),
+            Some((&"application/json", Quality::from_thousandths(100).unwrap()))
+        );
+        assert_eq!(
+            negotiator
+                .negotiate_detailed("*;q=0.1")
+                .unwrap()
+                .map(|n| n.range),
+            Some("*/*".to_owned())
+        );
+        assert!(negotiator.negotiate("*/html").is_err());
+    }
+
+    #[test]
+    fn negotiate_wildcard_params() {
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::new([
+                "text/plain",
+                "text/plain;charset=latin1",
+                "text/html;charset=utf-8;level=1"
+            ])
+            .unwrap()
+            .negotiate("text/*;charset=utf-8")
+            .unwrap(),
+            Some(&"text/html;charset=utf-8;level=1")
+        );
+
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::new([
+                "application/json",
+                "text/plain;charset=utf-8"
+            ])
+            .unwrap()
+            .negotiate("*/*;charset=utf-8")
+            .unwrap(),
+            Some(&"text/plain;charset=utf-8")
+        );
+
+        assert!(Negotiator::<ContentTypeNegotiation, _>::new([
+            "text/plain",
+            "text/plain;charset=latin1",
+            "application/json;charset=utf-8"
+        ])
+        .unwrap()
+        .negotiate("text/*;charset=utf-8")
+        .unwrap()
+        .is_none());
+
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::new([
+                "text/plain",
+                "text/plain;charset=utf-8"
+            ])
+            .unwrap()
+            .negotiate("text/*;charset=utf-8;q=0.5, text/*")
+            .unwrap(),
+            Some(&"text/plain")
+        );
+    }
+
+    #[test]
+    fn negotiate_all() {
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::new([
+                "application/json",
+                "text/html",
+                "text/plain",
+                "image/png"
+            ])
+            .unwrap()
+            .negotiate_all("text/*;q=0.5, text/html, application/json;q=0.8")
+            .unwrap(),
+            vec![&"text/html", &"application/json", &"text/plain"]
+        );
+
+        assert!(
+            Negotiator::<ContentTypeNegotiation, _>::new(["application/json", "text/html"])
+                .unwrap()
+                .negotiate_all("audio/*")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn validate_config() {
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, String>::validate_config(
+                "text/html\n# Fallback.\ntext/*\n\napplication/json\ntext/plain;q=1\njson".lines()
+            ),
+            vec![
+                (2, Error::InvalidWildcard),
+                (5, Error::QualityNotAllowed),
+                (6, Error::MissingSeparator('/')),
+            ]
+        );
+
+        assert!(
+            Negotiator::<ContentTypeNegotiation, String>::validate_config([
+                "text/html",
+                "application/json"
+            ])
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn negotiate_with_quality() {
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::new(["application/json", "text/plain"])
+                .unwrap()
+                .negotiate_with_quality("text/html, text/*;q=0.4")
+                .unwrap(),
+            Some((&"text/plain", Quality::from_thousandths(400).unwrap()))
+        );
+
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::new(["application/json"])
+                .unwrap()
+                .negotiate_with_quality("text/html")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn negotiate_weighted() {
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::new_weighted([
+            ("application/json", 0.8),
+            ("text/html", 1.),
+            ("text/plain", 0.),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            negotiator.negotiate("application/json, text/html").unwrap(),
+            Some(&"text/html")
+        );
+        assert_eq!(negotiator.negotiate("*/*").unwrap(), Some(&"text/html"));
+        assert_eq!(
+            negotiator
+                .negotiate("text/html;q=0.5, application/json")
+                .unwrap(),
+            Some(&"application/json")
+        );
+        assert_eq!(negotiator.negotiate("text/plain").unwrap(), None);
+        assert_eq!(
+            negotiator.negotiate_all("*/*").unwrap(),
+            vec![&"text/html", &"application/json"]
+        );
+
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::new_weighted([("text/html", 1.5)])
+                .unwrap_err(),
+            Error::InvalidWeight
+        );
+    }
+
+    #[test]
+    fn combine_fields() {
+        assert_eq!(
+            crate::combine_fields(["text/html", " ", "text/*;q=0.5"]),
+            "text/html,text/*;q=0.5"
+        );
+        assert!(matches!(
+            crate::combine_fields(["", "text/html"]),
+            Cow::Borrowed("text/html")
+        ));
+        assert_eq!(crate::combine_fields([]), "");
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn combined_header() {
+        use http::{header::ACCEPT, HeaderMap, HeaderValue};
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(crate::combined_header(&headers, ACCEPT), None);
+        headers.append(ACCEPT, HeaderValue::from_static("text/html"));
+        assert_eq!(
+            crate::combined_header(&headers, ACCEPT).as_deref(),
+            Some("text/html")
+        );
+        headers.append(
+            ACCEPT,
+            HeaderValue::from_bytes(b"text/plain;x=\xe9").unwrap(),
+        );
+        assert_eq!(
+            crate::combined_header(&headers, ACCEPT).as_deref(),
+            Some("text/html,text/plain;x=\u{e9}")
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn negotiate_from_headers() {
+        use http::{header::ACCEPT, HeaderMap, HeaderName, HeaderValue};
+
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["application/json", "text/html"])
+                .unwrap();
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            negotiator.negotiate_from_headers(&headers).unwrap(),
+            Some(&"application/json")
+        );
+
+        headers.append(ACCEPT, HeaderValue::from_static("application/json;q=0.5"));
+        headers.append(ACCEPT, HeaderValue::from_static("text/*"));
+        assert_eq!(
+            negotiator.negotiate_from_headers(&headers).unwrap(),
+            Some(&"text/html")
+        );
+
+        headers.append(ACCEPT, HeaderValue::from_bytes(b"text/\xff").unwrap());
+        assert_eq!(
+            negotiator.negotiate_from_headers(&headers).unwrap(),
+            Some(&"text/html")
+        );
+
+        let negotiator = negotiator.with_header(HeaderName::from_static("x-accept"));
+        assert_eq!(negotiator.header_name(), "x-accept");
+        headers.insert("x-accept", HeaderValue::from_static("text/html"));
+        assert_eq!(
+            negotiator.negotiate_from_headers(&headers).unwrap(),
+            Some(&"text/html")
+        );
+    }
+
+    #[test]
+    fn decode_params() {
+        assert_eq!(
+            decode_param("title", "caf%C3%A9").unwrap(),
+            ("title", Cow::Owned("café".to_owned()))
+        );
+        assert_eq!(
+            decode_param("title*", "iso-8859-1'fr'caf%E9").unwrap(),
+            ("title", Cow::Owned("café".to_owned()))
+        );
+        assert_eq!(
+            decode_param("level", "1").unwrap(),
+            ("level", Cow::Borrowed("1"))
+        );
+        assert_eq!(
+            decode_param("title", "%FF").unwrap_err(),
+            Error::InvalidHeader
+        );
+        assert_eq!(
+            decode_param("title", "%G1").unwrap_err(),
+            Error::InvalidHeader
+        );
+        assert_eq!(
+            decode_param("title*", "café").unwrap_err(),
+            Error::InvalidHeader
+        );
+
+        let negotiator = |decode| {
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .supported("text/plain;tenant=café")
+                .decode_params(decode)
+                .build()
+                .unwrap()
+        };
+        assert!(negotiator(false)
+            .negotiate("text/plain;tenant=caf%C3%A9")
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            negotiator(true)
+                .negotiate("text/plain;tenant=caf%C3%A9")
+                .unwrap(),
+            Some(&"text/plain;tenant=café")
+        );
+        assert_eq!(
+            negotiator(true)
+                .negotiate("text/*;tenant*=utf-8''caf%C3%A9")
+                .unwrap(),
+            Some(&"text/plain;tenant=café")
+        );
+        assert!(negotiator(true)
+            .negotiate("text/plain;tenant=caf%E9")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn negotiate_excluded() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["text/html", "application/json"])
+                .unwrap();
+        assert_eq!(
+            negotiator.negotiate("*/*, text/html;q=0").unwrap(),
+            Some(&"application/json")
+        );
+        assert!(negotiator.negotiate("text/html;q=0").unwrap().is_none());
+        assert_eq!(
+            negotiator.negotiate("text/html, */*;q=0").unwrap(),
+            Some(&"text/html")
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let html =
+            Negotiator::<ContentTypeNegotiation, _>::new(["text/html", "text/plain"]).unwrap();
+        let json = Negotiator::<ContentTypeNegotiation, _>::new(["application/json", "text/plain"])
+            .unwrap();
+        let merged = |policy| {
+            html.clone()
+                .merge(json.clone(), policy)
+                .map(|n| n.supported().copied().collect::<Vec<_>>())
+        };
+
+        assert_eq!(
+            merged(DuplicatePolicy::Keep).unwrap(),
+            vec!["text/html", "text/plain", "application/json", "text/plain"]
+        );
+        assert_eq!(
+            merged(DuplicatePolicy::Error).unwrap_err(),
+            Error::DuplicateSupported
+        );
+        assert_eq!(
+            merged(DuplicatePolicy::Ignore).unwrap(),
+            vec!["text/html", "text/plain", "application/json"]
+        );
+        assert_eq!(
+            html.merge(
+                Negotiator::new(["text/plain;charset=utf-8", "text/html"]).unwrap(),
+                DuplicatePolicy::LastWins
+            )
+            .unwrap()
+            .supported()
+            .collect::<Vec<_>>(),
+            vec![&"text/html", &"text/plain", &"text/plain;charset=utf-8"]
+        );
+    }
+
+    #[test]
+    fn negotiate_bytes() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["text/html", "text/plain;title=café"])
+                .unwrap();
+        assert_eq!(
+            negotiator
+                .negotiate_bytes(b"text/plain;title=caf\xe9, text/html;q=0.5")
+                .unwrap(),
+            Some(&"text/plain;title=café")
+        );
+        assert_eq!(
+            negotiator
+                .negotiate_bytes("text/plain;title=café".as_bytes())
+                .unwrap(),
+            Some(&"text/plain;title=café")
+        );
+        assert_eq!(
+            negotiator.negotiate_bytes(b"\xe9").unwrap_err(),
+            Error::MissingSeparator('/')
+        );
+    }
+
+    #[test]
+    fn validate_header() {
+        assert_eq!(
+            ContentTypeNegotiation::validate_header("text/html, application/*;q=0.5"),
+            Ok(())
+        );
+        assert_eq!(
+            ContentTypeNegotiation::validate_header("text/html, */plain"),
+            Err(Error::InvalidWildcard)
+        );
+        assert_eq!(
+            ContentTypeNegotiation::validate_header(r#"text/html;title="a,*/plain", app/json"#),
+            Ok(())
+        );
+        assert_eq!(
+            ContentTypeNegotiation::sanitize_header("text/html,*/plain, application/*;q=0.5"),
+            "text/html, application/*;q=0.5"
+        );
+        assert_eq!(
+            ContentTypeNegotiation::sanitize_header(r#"text/html;title="a,b", */plain, app/json"#),
+            r#"text/html;title="a,b", app/json"#
+        );
+        assert_eq!(
+            ContentTypeNegotiation::canonicalize_header(
+                "Text/HTML;Level=1 ;q=0.5,application/json,  */*;q=0.1, TEXT/html;level=1;q=0.8, \
+                 text/plain;charset=\"UTF-8\""
+            ),
+            Ok(
+                "text/plain;charset=utf-8, application/json, text/html;level=1;q=0.8, */*;q=0.1"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            ContentTypeNegotiation::canonicalize_header("text/html, */plain"),
+            Err(Error::InvalidWildcard)
+        );
+
+        let negotiator = Negotiator::<ContentTypeNegotiation, &str>::builder()
+            .wildcard_policy(WildcardPolicy::Reject)
+            .build()
+            .unwrap();
+        assert_eq!(negotiator.validate_header("text/html"), Ok(()));
+        assert_eq!(
+            negotiator.validate_header("text/*"),
+            Err(Error::InvalidWildcard)
+        );
+    }
+
+    #[test]
+    fn accept_extensions() {
+        assert_eq!(
+            HeaderElements::<ContentTypeNegotiation>::new("text/html;level=1;q=0.5;ext=x;flag")
+                .next()
+                .unwrap()
+                .unwrap(),
+            (
+                (
+                    MaybeWildcard::Specific("text"),
+                    MaybeWildcard::Specific("html"),
+                    HeaderParams::parse(";level=1").unwrap(),
+                    HeaderParams::parse(";ext=x;flag=").unwrap(),
+                ),
+                Quality::from_thousandths(500).unwrap()
+            )
+        );
+
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["text/html;level=1", "text/plain"])
+                .unwrap();
+        assert_eq!(
+            negotiator
+                .negotiate("text/html;level=1;q=1;ext=x, text/plain;q=0.5")
+                .unwrap(),
+            Some(&"text/html;level=1")
+        );
+        assert_eq!(
+            negotiator
+                .negotiate("text/html;q=1;level=1, text/plain;q=0.5")
+                .unwrap(),
+            Some(&"text/plain")
+        );
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["Text/HTML;Level=1", "application/json"])
+                .unwrap();
+        assert_eq!(
+            negotiator.negotiate("TEXT/html;LEVEL=1").unwrap(),
+            Some(&"Text/HTML;Level=1")
+        );
+        assert_eq!(
+            negotiator
+                .negotiate("text/*;level=1;Q=0.5, Application/JSON;q=0.4")
+                .unwrap(),
+            Some(&"Text/HTML;Level=1")
+        );
+        assert!(negotiator.negotiate("text/html;level=2").unwrap().is_none());
+        assert_eq!(
+            negotiator.get("text/html;level=1"),
+            Some(&"Text/HTML;Level=1")
+        );
+        assert_eq!(
+            Negotiator::<ContentTypeNegotiation, _>::new(["text/html;Q=1"]).unwrap_err(),
+            Error::QualityNotAllowed
+        );
+    }
+
+    #[test]
+    fn get() {
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::new([
+            "text/html",
+            "text/plain;format=flowed;charset=utf-8",
+        ])
+        .unwrap();
+        assert_eq!(negotiator.get("text/html"), Some(&"text/html"));
+        assert_eq!(
+            negotiator.get("text/plain;charset=utf-8;format=flowed"),
+            Some(&"text/plain;format=flowed;charset=utf-8")
+        );
+        assert_eq!(negotiator.get("text/plain"), None);
+        assert_eq!(negotiator.get("text"), None);
+    }
+
+    #[test]
+    fn map() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["application/json", "text/plain"])
+                .unwrap()
+                .with_default("text/plain")
+                .map(|mime| -> fn(&str) -> String {
+                    match mime {
+                        "application/json" => |s| format!("{{\"message\":\"{s}\"}}"),
+                        _ => |s| s.to_owned(),
+                    }
+                });
+        let render = negotiator.negotiate("application/*").unwrap().unwrap();
+        assert_eq!(render("hi"), r#"{"message":"hi"}"#);
+        assert_eq!(negotiator.default_value().unwrap()("hi"), "hi");
+    }
+
+    #[test]
+    fn negotiate_detailed() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["application/json", "text/html"])
+                .unwrap();
+        let negotiated = negotiator
+            .negotiate_detailed("application/json;q=0.5, text/*;level=1;q=0.8, text/html;q=0.8")
+            .unwrap()
+            .unwrap();
+        assert_eq!(negotiated.value, &"text/html");
+        assert_eq!(negotiated.quality, Quality::from_thousandths(800).unwrap());
+        assert_eq!(negotiated.range, "text/html");
+
+        let negotiated = negotiator
+            .negotiate_detailed("application/xml, */*;q=0.1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(negotiated.range, "*/*");
+    }
+
+    #[test]
+    fn explicit_default() {
+        use crate::DynNegotiator;
+
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["text/html", "application/json"])
+                .unwrap()
+                .with_default("application/json");
+        let json = Some(&"application/json");
+        assert_eq!(negotiator.negotiate("*/*").unwrap(), json);
+        assert_eq!(
+            negotiator.negotiate_with_quality("*/*;q=0.5").unwrap(),
+            Some((&"application/json", Quality::from_thousandths(500).unwrap()))
+        );
+        assert_eq!(
+            negotiator.negotiate_detailed("*/*").unwrap().unwrap().range,
+            "*/*"
+        );
+        assert_eq!(negotiator.negotiate_lenient("*/*").unwrap().0, json);
+        assert_eq!(
+            negotiator
+                .negotiate_parsed(&negotiator.parse_shared("*/*").unwrap())
+                .unwrap(),
+            json
+        );
+        assert_eq!(
+            negotiator.negotiate_charset("*/*", None).unwrap(),
+            Some((&"application/json", None))
+        );
+        assert_eq!(negotiator.negotiate_index("*/*").unwrap(), Some(1));
+        assert_eq!(
+            negotiator.simulate([("*/*", 1.)]).share("application/json"),
+            Some(1.)
+        );
+
+        // Nothing matches, the default comes without a range.
+        let negotiated = negotiator.negotiate_detailed("image/png").unwrap().unwrap();
+        assert_eq!(negotiated.value, &"application/json");
+        assert_eq!(negotiated.quality, Quality::ZERO);
+        assert_eq!(negotiated.range, "");
+
+        // A q=0 range excluding the default prevails.
+        let header = "*/*, application/json;q=0";
+        assert_eq!(negotiator.negotiate(header).unwrap(), Some(&"text/html"));
+        assert_eq!(negotiator.negotiate_index(header).unwrap(), Some(0));
+        assert_eq!(
+            negotiator
+                .negotiate("image/png, application/*;q=0")
+                .unwrap(),
+            None
+        );
+
+        let negotiator = negotiator.with_default("text/plain");
+        assert_eq!(negotiator.negotiate_index("*/*").unwrap(), None);
+        assert_eq!(
+            negotiator.simulate([("*/*", 1.)]).share("text/plain"),
+            Some(1.)
+        );
+    }
+
+    #[test]
+    fn negotiate_parsed() {
+        let html = Negotiator::<ContentTypeNegotiation, _>::new(["text/html"]).unwrap();
+        let json = Negotiator::<ContentTypeNegotiation, _>::new(["application/json"]).unwrap();
+        let header = html.parse_shared("text/html, application/*;q=0.5").unwrap();
+        assert_eq!(header.len(), 2);
+        assert_eq!(html.negotiate_parsed(&header).unwrap(), Some(&"text/html"));
+        assert_eq!(
+            json.negotiate_parsed(&header).unwrap(),
+            Some(&"application/json")
+        );
+
+        let (header, skipped) =
+            ParsedHeader::parse_lenient(HeaderElements::new("text, text/html")).unwrap();
+        assert_eq!(header.len(), 1);
+        assert_eq!(skipped, vec![(0, Error::MissingSeparator('/'))]);
+        assert!(json.negotiate_parsed(&header).unwrap().is_none());
+
+        assert_eq!(
+            ParsedHeader::<ContentTypeNegotiation>::parse(HeaderElements::new("text")).err(),
+            Some(Error::MissingSeparator('/'))
+        );
+
+        let limits = Limits {
+            max_elements: 1,
+            ..Limits::UNBOUNDED
+        };
+        let elements = || HeaderElements::with_limits("text/html, text, */*", limits);
+        assert_eq!(
+            ParsedHeader::<ContentTypeNegotiation>::parse_lenient(elements()).err(),
+            Some(Error::LimitExceeded)
+        );
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .supported("text/html")
+            .limits(limits)
+            .lenient(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            negotiator.parse_shared("text/html, */*").err(),
+            Some(Error::LimitExceeded)
+        );
+        assert_eq!(negotiator.parse_shared("text/html").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn charset() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["text/html", "text/plain;charset=UTF-8"])
+                .unwrap();
+        assert_eq!(
+            negotiator.negotiate("text/html;charset=utf-8").unwrap(),
+            Some(&"text/html")
+        );
+        assert_eq!(
+            negotiator.negotiate("text/plain;charset=utf-8").unwrap(),
+            Some(&"text/plain;charset=UTF-8")
+        );
+        assert!(negotiator
+            .negotiate("text/plain;charset=latin1")
+            .unwrap()
+            .is_none());
+
+        assert_eq!(
+            negotiator
+                .negotiate_charset("text/html;charset=ISO-8859-1", None)
+                .unwrap(),
+            Some((&"text/html", Some("iso-8859-1".to_owned())))
+        );
+        assert_eq!(
+            negotiator.negotiate_charset("text/plain", None).unwrap(),
+            Some((&"text/plain;charset=UTF-8", Some("utf-8".to_owned())))
+        );
+        assert_eq!(
+            negotiator.negotiate_charset("text/html", None).unwrap(),
+            Some((&"text/html", None))
+        );
+        assert_eq!(
+            negotiator
+                .negotiate_charset("text/html;charset=utf-8", Some("iso-8859-1, *;q=0"))
+                .unwrap(),
+            Some((&"text/html", None))
+        );
+        assert_eq!(
+            negotiator
+                .negotiate_charset("text/html;charset=utf-8", Some("iso-8859-1, *;q=0.5"))
+                .unwrap(),
+            Some((&"text/html", Some("utf-8".to_owned())))
+        );
+    }
+
+    #[test]
+    fn negotiate_version() {
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::new([
+            "application/vnd.api+json;version=1",
+            "application/vnd.api+json;version=3",
+            "application/vnd.api+json;version=2",
+            "application/json",
+        ])
+        .unwrap();
+        let negotiate = |header| negotiator.negotiate_version(header, "version").unwrap();
+        assert_eq!(
+            negotiate("application/vnd.api+json;version=2"),
+            Some(&"application/vnd.api+json;version=2")
+        );
+        assert_eq!(
+            negotiate("application/vnd.api+json;version=10"),
+            Some(&"application/vnd.api+json;version=3")
+        );
+        assert_eq!(
+            negotiate("application/vnd.api+json"),
+            Some(&"application/vnd.api+json;version=3")
+        );
+        assert_eq!(negotiate("application/vnd.api+json;version=0"), None);
+        assert_eq!(
+            negotiate("application/vnd.api+json;version=0, application/json;q=0.5"),
+            Some(&"application/json")
+        );
+        assert_eq!(
+            negotiate("application/vnd.api+json;version=x, */*;q=0.1"),
+            Some(&"application/vnd.api+json;version=3")
+        );
+        assert_eq!(
+            negotiate("application/json;q=0.5, application/vnd.api+json;version=1"),
+            Some(&"application/vnd.api+json;version=1")
+        );
+    }
+
+    #[test]
+    fn negotiate_version_options() {
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .supported("application/vnd.api+json;version=1")
+            .supported_weighted("application/vnd.api+json;version=2", 0.8)
+            .supported_weighted("application/vnd.api+json;version=3", 0.)
+            .supported_between(
+                "application/vnd.api+json;version=4",
+                Availability::until(SystemTime::UNIX_EPOCH),
+            )
+            .supported_weighted("application/json", 0.5)
+            .weighting(Weighting::Multiplicative)
+            .build()
+            .unwrap();
+        let negotiate = |header| negotiator.negotiate_version(header, "version").unwrap();
+        assert_eq!(
+            negotiate("application/vnd.api+json;version=10"),
+            Some(&"application/vnd.api+json;version=2")
+        );
+        assert_eq!(
+            negotiate("application/vnd.api+json;q=0.5, application/json"),
+            Some(&"application/json")
+        );
+        assert_eq!(
+            negotiate("application/vnd.api+json;q=0.7, application/json"),
+            Some(&"application/vnd.api+json;version=2")
+        );
+
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .extend(["application/json", "application/vnd.api+json;version=1"])
+            .tie_break(TieBreak::ServerOrder)
+            .build()
+            .unwrap();
+        assert_eq!(
+            negotiator
+                .negotiate_version("application/vnd.api+json, application/json", "version")
+                .unwrap(),
+            Some(&"application/json")
+        );
+    }
+
+    #[test]
+    fn quoted_params() {
+        let supported = r#"text/plain;title="he said \"hi\", twice""#;
+        let media_type = supported.parse::<MediaType>().unwrap();
+        assert_eq!(media_type.param("title"), Some(r#"he said "hi", twice"#));
+        assert_eq!(media_type.to_string(), supported);
+
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::new([supported]).unwrap();
+        assert_eq!(
+            negotiator
+                .negotiate(r#"text/plain;title="he said \"hi\", twice", text/html"#)
+                .unwrap(),
+            Some(&supported)
+        );
+        assert_eq!(
+            negotiator
+                .negotiate(r#"text/plain;title="he said \"hi\"""#)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            negotiator
+                .negotiate_charset(
+                    r#"text/plain;charset="UTF-8";title="he said \"hi\", twice""#,
+                    None
+                )
+                .unwrap(),
+            Some((&supported, Some("utf-8".to_owned())))
+        );
+    }
+
+    #[test]
+    fn negotiate_profile() {
+        let expanded = "application/ld+json;profile=\"http://www.w3.org/ns/json-ld#expanded\"";
+        let compacted_flattened =
+            "application/ld+json;profile=\"http://www.w3.org/ns/json-ld#compacted \
+             http://www.w3.org/ns/json-ld#flattened\"";
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::new([
+            expanded,
+            compacted_flattened,
+            "application/json",
+        ])
+        .unwrap();
+        let flattened = "application/ld+json;profile=\"http://www.w3.org/ns/json-ld#flattened\"";
+        assert_eq!(
+            negotiator.negotiate(flattened).unwrap(),
+            Some(&compacted_flattened)
+        );
+        assert_eq!(
+            negotiator
+                .negotiate("application/ld+json;profile=http://www.w3.org/ns/json-ld#expanded")
+                .unwrap(),
+            Some(&expanded)
+        );
+        assert_eq!(
+            negotiator.negotiate("application/ld+json").unwrap(),
+            Some(&expanded)
+        );
+        let other = "application/ld+json;profile=\"http://example.com/other\", \
+                     application/json;q=0.5";
+        assert_eq!(
+            negotiator.negotiate(other).unwrap(),
+            Some(&"application/json")
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        let sorted = |header| {
+            parse_and_sort_header(HeaderElements::<ContentTypeNegotiation>::new(header), None)
+                .unwrap()
+                .iter()
+                .map(|(r, _q)| ContentTypeNegotiation::format_range(r))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            sorted("*/*;a=1;b=2, text/html"),
+            vec!["text/html", "*/*;a=1;b=2"]
+        );
+        assert_eq!(
+            sorted("text/*;a=1;b=2;c=3, */*;a=1;b=2;c=3;d=4, text/html;a=1, text/plain"),
+            vec![
+                "text/html;a=1",
+                "text/plain",
+                "text/*;a=1;b=2;c=3",
+                "*/*;a=1;b=2;c=3;d=4"
+            ]
+        );
+
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["application/json;a=1;b=2", "text/html"])
+                .unwrap();
+        assert_eq!(
+            negotiator.negotiate("*/*;a=1;b=2, text/html").unwrap(),
+            Some(&"text/html")
+        );
+        assert_eq!(
+            negotiator
+                .negotiate("*/*;a=1;b=2, text/*;a=1, text/html;q=0")
+                .unwrap(),
+            Some(&"application/json;a=1;b=2")
+        );
+    }
+
+    #[test]
+    fn negotiate_unsorted() {
+        let headers = [
+            "text/html;q=0.5, application/json;q=0.5, */*;q=0.5",
+            "*/*, text/*;q=0.2, text/html;q=0",
+            "text/plain;q=0.1, text/*, text/plain, application/*;q=0.9",
+            "application/json;q=0.3, application/json;q=0.8, text/*;q=0.8",
+        ];
+        for tie_break in [
+            TieBreak::ClientOrder,
+            TieBreak::ServerOrder,
+            TieBreak::MostSpecific,
+        ] {
+            for scoring in [Scoring::Rfc9110, Scoring::FirstMatch] {
+                let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+                    .extend([
+                        "text/plain",
+                        "text/html",
+                        "application/json;v=1",
+                        "application/json",
+                    ])
+                    .tie_break(tie_break)
+                    .scoring(scoring)
+                    .build()
+                    .unwrap();
+                for header in headers {
+                    let sorted = negotiator.parse_shared(header).unwrap();
+                    assert_eq!(
+                        negotiator.negotiate(header).unwrap(),
+                        negotiator.negotiate_parsed(&sorted).unwrap(),
+                        "{header} {tie_break:?} {scoring:?}"
+                    );
+                    assert_eq!(
+                        negotiator.negotiate(header).unwrap(),
+                        negotiator.negotiate_all(header).unwrap().first().copied(),
+                        "{header} {tie_break:?} {scoring:?}"
+                    );
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,193 @@
+use std::{iter::Enumerate, marker::PhantomData};
+
+use crate::{
+    params::{split_unquoted, SplitUnquoted},
+    Error, Limits, NegotiationType, Quality,
+};
+
+/// Lazily parses the comma-separated elements of a header in the order the client sent them,
+/// without collecting or sorting them.
+///
+/// An empty header yields the range it stands for, if any, see
+/// [`NegotiationType::empty_header`]. A malformed element yields its error and parsing goes on
+/// with the next one, while exceeding a limit yields [`Error::LimitExceeded`] and ends the
+/// iteration.
+#[derive(Debug)]
+pub struct HeaderElements<'a, N> {
+    header: &'a str,
+    elements: Enumerate<SplitUnquoted<'a>>,
+    limits: Limits,
+    strict_quality: bool,
+    browser_quirks: bool,
+    done: bool,
+    _negotiation: PhantomData<N>,
+}
+
+impl<'a, N: NegotiationType> HeaderElements<'a, N> {
+    pub fn new(header: &'a str) -> Self {
+        Self::with_limits(header, Limits::UNBOUNDED)
+    }
+
+    pub fn with_limits(header: &'a str, limits: Limits) -> Self {
+        Self {
+            header,
+            elements: split_unquoted(header, ',').enumerate(),
+            limits,
+            strict_quality: false,
+            browser_quirks: false,
+            done: false,
+            _negotiation: PhantomData,
+        }
+    }
+
+    /// Fails elements whose quality does not follow the RFC 9110 grammar with
+    /// [`Error::InvalidQuality`], instead of clamping it.
+    pub fn strict_quality(mut self, strict: bool) -> Self {
+        self.strict_quality = strict;
+        self
+    }
+
+    /// Parses elements with [`NegotiationType::parse_quirky_header_elem`] and skips empty ones,
+    /// e.g. from a trailing comma.
+    pub fn browser_quirks(mut self, enabled: bool) -> Self {
+        self.browser_quirks = enabled;
+        self
+    }
+}
+
+fn check_quality(elem: &str) -> Result<(), Error> {
+    for param in split_unquoted(elem, ';').skip(1) {
+        match param.split_once('=') {
+            Some((k, v)) if k.trim().eq_ignore_ascii_case("q") => {
+                v.trim().parse::<Quality>()?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+impl<'a, N: NegotiationType> Iterator for HeaderElements<'a, N> {
+    type Item = Result<(N::Range<'a>, Quality), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let elem = loop {
+            let Some((i, elem)) = self.elements.next() else {
+                self.done = true;
+                return None;
+            };
+            if (i == 0 && self.header.len() > self.limits.max_length)
+                || i >= self.limits.max_elements
+                || split_unquoted(elem, ';').count() - 1 > self.limits.max_params
+            {
+                self.done = true;
+                return Some(Err(Error::LimitExceeded));
+            }
+            if self.header.trim().is_empty() {
+                self.done = true;
+                return N::empty_header().map(Ok);
+            }
+            if !(self.browser_quirks && elem.trim().is_empty()) {
+                break elem;
+            }
+        };
+        if self.strict_quality {
+            if let Err(err) = check_quality(elem) {
+                return Some(Err(err));
+            }
+        }
+        match self.browser_quirks {
+            true => Some(N::parse_quirky_header_elem(elem)),
+            false => Some(N::parse_header_elem(elem)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeaderElements;
+    use crate::{
+        ContentTypeNegotiation, EncodingNegotiation, Error, HeaderParams, Limits, MaybeWildcard,
+        Quality,
+    };
+
+    #[test]
+    fn elements() {
+        let mut elements = HeaderElements::<EncodingNegotiation>::new("gzip;q=0.5, br;x=1, *");
+        assert_eq!(
+            elements.next(),
+            Some(Ok((
+                MaybeWildcard::Specific("gzip"),
+                Quality::from_thousandths(500).unwrap()
+            )))
+        );
+        assert_eq!(elements.next(), Some(Err(Error::ParamsNotAllowed)));
+        assert_eq!(
+            elements.next(),
+            Some(Ok((MaybeWildcard::Wildcard, Quality::ONE)))
+        );
+        assert_eq!(elements.next(), None);
+
+        let mut elements = HeaderElements::<ContentTypeNegotiation>::new(" ");
+        assert_eq!(
+            elements.next(),
+            Some(Ok((
+                (
+                    MaybeWildcard::Wildcard,
+                    MaybeWildcard::Wildcard,
+                    HeaderParams::default(),
+                    HeaderParams::default()
+                ),
+                Quality::ONE
+            )))
+        );
+        assert_eq!(elements.next(), None);
+    }
+
+    #[test]
+    fn limits() {
+        let limits = Limits {
+            max_elements: 2,
+            ..Limits::UNBOUNDED
+        };
+        assert_eq!(
+            HeaderElements::<EncodingNegotiation>::with_limits("gzip, br, deflate, *", limits)
+                .map(|elem| elem.map(|(range, _q)| range))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(MaybeWildcard::Specific("gzip")),
+                Ok(MaybeWildcard::Specific("br")),
+                Err(Error::LimitExceeded),
+            ]
+        );
+
+        let limits = Limits {
+            max_params: 1,
+            ..Limits::UNBOUNDED
+        };
+        let mut elements = HeaderElements::<ContentTypeNegotiation>::with_limits(
+            r#"text/html;title="a;b;c", text/plain;a=1;b=2"#,
+            limits,
+        );
+        assert!(elements.next().unwrap().is_ok());
+        assert_eq!(elements.next(), Some(Err(Error::LimitExceeded)));
+    }
+
+    #[test]
+    fn browser_quirks() {
+        let header = format!("gzip,{}br", ",".repeat(1_000_000));
+        assert_eq!(
+            HeaderElements::<EncodingNegotiation>::new(&header)
+                .browser_quirks(true)
+                .map(|elem| elem.map(|(range, _q)| range))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(MaybeWildcard::Specific("gzip")),
+                Ok(MaybeWildcard::Specific("br")),
+            ]
+        );
+    }
+}
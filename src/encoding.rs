@@ -1,10 +1,13 @@
-use crate::{match_first, AsNegotiationStr, Error, MaybeWildcard, NegotiationType};
+use crate::{AsNegotiationStr, Error, MaybeWildcard, NegotiationKind, NegotiationType, Quality};
 
 #[derive(Copy, Clone, Debug)]
 pub struct EncodingNegotiation;
 
 impl NegotiationType for EncodingNegotiation {
     type Parsed = String;
+    type Range<'a> = MaybeWildcard<&'a str>;
+
+    const KIND: NegotiationKind = NegotiationKind::Encoding;
 
     fn parse_elem<M: AsNegotiationStr>(input: &M) -> Result<Self::Parsed, Error> {
         let input = input.as_str();
@@ -17,38 +20,61 @@ impl NegotiationType for EncodingNegotiation {
         Ok(input.to_owned())
     }
 
-    fn parse_and_negotiate_header<'a, T>(
-        supported: &'a [(Self::Parsed, T)],
-        header: &str,
-    ) -> Result<Option<&'a T>, Error> {
-        let mut methods = header
-            .split(',')
-            .map(|entry| {
-                let mut parts = entry.split(';').map(str::trim);
-                let main = MaybeWildcard::from_str(parts.next().ok_or(Error::InvalidHeader)?);
-                let q = match parts.next() {
-                    Some(first_param) => {
-                        let (k, v) = first_param.split_once('=').ok_or(Error::InvalidHeader)?;
-                        if k != "q" || parts.next().is_some() {
-                            return Err(Error::ParamsNotAllowed);
-                        }
-                        v.parse::<f32>()
-                            .map_err(|err| Error::InvalidQuality { source: err })?
-                    }
-                    None => 1.,
-                };
-                Ok((main, q))
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        methods.sort_by(|(_, q1), (_, q2)| q1.total_cmp(q2).reverse());
-        Ok(match_first(
-            supported,
-            methods.iter().map(|(m, _q)| m),
-            |s, h| h.matches(s),
-        ))
-    }
-
-    #[cfg(feature = "axum")]
+    fn parse_header_elem(input: &str) -> Result<(Self::Range<'_>, Quality), Error> {
+        let (main, q) = Quality::split_weight(input, false)?;
+        Ok((MaybeWildcard::from_str(main), q))
+    }
+
+    fn parse_quirky_header_elem(input: &str) -> Result<(Self::Range<'_>, Quality), Error> {
+        let (main, q) = Quality::split_weight(input, true)?;
+        Ok((MaybeWildcard::from_str(main), q))
+    }
+
+    // An empty `Accept-Encoding` only accepts the identity coding.
+    fn empty_header<'a>() -> Option<(Self::Range<'a>, Quality)> {
+        Some((MaybeWildcard::Specific("identity"), Quality::ONE))
+    }
+
+    // The identity coding is acceptable unless excluded by `identity;q=0` or `*;q=0` (RFC 9110
+    // section 12.5.3). Like the `negotiator` npm package, it gets the lowest listed quality.
+    fn implicit_range<'a>(
+        ranges: &[(Self::Range<'a>, Quality)],
+    ) -> Option<(Self::Range<'a>, Quality)> {
+        if ranges.iter().any(|(r, _q)| match r {
+            MaybeWildcard::Specific(coding) => coding.eq_ignore_ascii_case("identity"),
+            MaybeWildcard::Wildcard => true,
+        }) {
+            return None;
+        }
+        let q = ranges
+            .iter()
+            .map(|(_r, q)| *q)
+            .filter(|q| *q > Quality::ZERO)
+            .min()
+            .unwrap_or(Quality::ONE);
+        Some((MaybeWildcard::Specific("identity"), q))
+    }
+
+    fn precedence(range: &Self::Range<'_>) -> (u8, usize) {
+        match range {
+            MaybeWildcard::Specific(_) => (1, 0),
+            MaybeWildcard::Wildcard => (0, 0),
+        }
+    }
+
+    fn matches(supported: &Self::Parsed, range: &Self::Range<'_>) -> bool {
+        range.matches(supported)
+    }
+
+    fn is_wildcard(range: &Self::Range<'_>) -> bool {
+        *range == MaybeWildcard::Wildcard
+    }
+
+    fn format_range(range: &Self::Range<'_>) -> String {
+        range.to_string()
+    }
+
+    #[cfg(feature = "http")]
     fn associated_header() -> http::header::HeaderName {
         http::header::ACCEPT_ENCODING
     }
@@ -57,15 +83,15 @@ impl NegotiationType for EncodingNegotiation {
 #[cfg(test)]
 mod tests {
     use super::EncodingNegotiation;
-    use crate::{Error, Negotiator};
+    use crate::{Entry, Error, NegotiationKind, Negotiator, Quality};
 
     #[test]
     fn new() {
         assert_eq!(
-            Negotiator::<EncodingNegotiation, _>::new(["gzip"])
+            *Negotiator::<EncodingNegotiation, _>::new(["gzip"])
                 .unwrap()
                 .supported,
-            vec![(("gzip".to_owned()), "gzip")]
+            vec![Entry::new("gzip".to_owned(), "gzip")]
         );
 
         assert_eq!(
@@ -97,6 +123,20 @@ mod tests {
             .unwrap()
             .is_none());
 
+        assert!(Negotiator::<EncodingNegotiation, _>::new(["gzip"])
+            .unwrap()
+            .negotiate("")
+            .unwrap()
+            .is_none());
+
+        assert_eq!(
+            Negotiator::<EncodingNegotiation, _>::new(["gzip", "identity"])
+                .unwrap()
+                .negotiate("")
+                .unwrap(),
+            Some(&"identity")
+        );
+
         assert_eq!(
             Negotiator::<EncodingNegotiation, _>::new(["gzip"])
                 .unwrap()
@@ -137,4 +177,169 @@ mod tests {
             Some(&"gzip")
         );
     }
+
+    #[test]
+    fn implicit_identity() {
+        let negotiator = Negotiator::<EncodingNegotiation, _>::new(["identity", "gzip"]).unwrap();
+        assert_eq!(negotiator.negotiate("br").unwrap(), Some(&"identity"));
+        assert_eq!(negotiator.negotiate("gzip").unwrap(), Some(&"gzip"));
+        assert_eq!(
+            negotiator.negotiate("gzip;q=0.5, br").unwrap(),
+            Some(&"gzip")
+        );
+        assert!(negotiator.negotiate("br, identity;q=0").unwrap().is_none());
+        assert!(negotiator.negotiate("br, *;q=0").unwrap().is_none());
+    }
+
+    #[test]
+    fn negotiate_all() {
+        assert_eq!(
+            Negotiator::<EncodingNegotiation, _>::new(["gzip", "br", "deflate"])
+                .unwrap()
+                .negotiate_all("br;q=0.5, *;q=0.1")
+                .unwrap(),
+            vec![&"br", &"gzip", &"deflate"]
+        );
+    }
+
+    #[test]
+    fn negotiate_with_quality() {
+        assert_eq!(
+            Negotiator::<EncodingNegotiation, _>::new(["gzip", "br"])
+                .unwrap()
+                .negotiate_with_quality("gzip;q=0.3, identity")
+                .unwrap(),
+            Some((&"gzip", Quality::from_thousandths(300).unwrap()))
+        );
+    }
+
+    #[test]
+    fn negotiate_opt() {
+        let negotiator = Negotiator::<EncodingNegotiation, _>::new(["br", "gzip"]).unwrap();
+        assert_eq!(negotiator.negotiate_opt(None).unwrap(), Some(&"br"));
+        assert_eq!(
+            negotiator.negotiate_opt(Some("gzip")).unwrap(),
+            Some(&"gzip")
+        );
+        assert!(negotiator.negotiate_opt(Some("deflate")).unwrap().is_none());
+
+        let negotiator = negotiator.with_default("gzip");
+        assert_eq!(negotiator.negotiate_opt(None).unwrap(), Some(&"gzip"));
+        assert_eq!(negotiator.negotiate("*").unwrap(), Some(&"gzip"));
+        assert_eq!(negotiator.negotiate("br, *;q=0.5").unwrap(), Some(&"br"));
+        assert_eq!(negotiator.negotiate("deflate").unwrap(), Some(&"gzip"));
+    }
+
+    #[test]
+    fn mutate() {
+        let mut negotiator = Negotiator::<EncodingNegotiation, _>::new(["gzip"]).unwrap();
+        negotiator.push("br").unwrap();
+        negotiator.insert(0, "deflate").unwrap();
+        assert_eq!(
+            negotiator.negotiate_all("*").unwrap(),
+            vec![&"deflate", &"gzip", &"br"]
+        );
+
+        assert_eq!(negotiator.push("*").unwrap_err(), Error::InvalidWildcard);
+        assert_eq!(negotiator.len(), 3);
+
+        assert_eq!(negotiator.remove(0), "deflate");
+        negotiator.retain(|e| *e != "br");
+        assert_eq!(
+            negotiator.negotiate("br, gzip;q=0.1").unwrap(),
+            Some(&"gzip")
+        );
+        assert!(negotiator.negotiate("br").unwrap().is_none());
+
+        let shared = negotiator.clone();
+        negotiator.push("br").unwrap();
+        assert_eq!(shared.len(), 1);
+        assert_eq!(negotiator.len(), 2);
+    }
+
+    #[test]
+    fn negotiate_or_first() {
+        let negotiator = Negotiator::<EncodingNegotiation, _>::new(["gzip", "br"]).unwrap();
+        assert_eq!(negotiator.negotiate_or_first(Some("br")).unwrap(), &"br");
+        assert_eq!(
+            negotiator.negotiate_or_first(Some("zstd")).unwrap(),
+            &"gzip"
+        );
+        assert_eq!(negotiator.negotiate_or_first(None).unwrap(), &"gzip");
+        assert_eq!(
+            negotiator.negotiate_or_first(Some("br;q")).unwrap_err(),
+            Error::InvalidHeader
+        );
+        assert_eq!(
+            Negotiator::<EncodingNegotiation, &str>::new([])
+                .unwrap()
+                .negotiate_or_first(None)
+                .unwrap_err(),
+            Error::NotAcceptable
+        );
+    }
+
+    #[test]
+    fn negotiate_dyn() {
+        assert_eq!(
+            crate::negotiate_dyn(NegotiationKind::Encoding, &["gzip", "br"], "br").unwrap(),
+            Some(&"br")
+        );
+        assert_eq!(
+            crate::negotiate_dyn(
+                NegotiationKind::ContentType,
+                &["text/html".to_owned()],
+                "text/*"
+            )
+            .unwrap(),
+            Some(&"text/html".to_owned())
+        );
+        assert_eq!(
+            crate::negotiate_dyn(NegotiationKind::Language, &["en;q=1"], "en").unwrap_err(),
+            Error::ParamsNotAllowed
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn from_header_name() {
+        use http::header::{ACCEPT_ENCODING, CONTENT_TYPE};
+
+        assert_eq!(
+            NegotiationKind::from_header_name(&ACCEPT_ENCODING),
+            Some(NegotiationKind::Encoding)
+        );
+        assert_eq!(NegotiationKind::from_header_name(&CONTENT_TYPE), None);
+        assert_eq!(
+            NegotiationKind::Encoding.header_name(),
+            Some(ACCEPT_ENCODING)
+        );
+        assert_eq!(NegotiationKind::Token.header_name(), None);
+    }
+
+    #[test]
+    fn negotiate_excluded() {
+        let negotiator = Negotiator::<EncodingNegotiation, _>::new(["gzip", "br"]).unwrap();
+        assert_eq!(negotiator.negotiate("*, gzip;q=0").unwrap(), Some(&"br"));
+        assert!(negotiator.negotiate("gzip;q=0, br;q=0").unwrap().is_none());
+        assert_eq!(negotiator.negotiate_all("*;q=0, br").unwrap(), vec![&"br"]);
+    }
+
+    #[test]
+    fn iterate() {
+        let mut negotiator = Negotiator::<EncodingNegotiation, _>::new(["gzip"]).unwrap();
+        negotiator.try_extend(["br", "deflate"]).unwrap();
+        assert_eq!(
+            negotiator.try_extend(["zstd", "*"]).unwrap_err(),
+            Error::InvalidWildcard
+        );
+        assert_eq!(
+            (&negotiator).into_iter().collect::<Vec<_>>(),
+            vec![&"gzip", &"br", &"deflate"]
+        );
+        assert_eq!(
+            negotiator.into_iter().collect::<Vec<_>>(),
+            vec!["gzip", "br", "deflate"]
+        );
+    }
 }
@@ -1,8 +1,10 @@
-use std::str::FromStr;
+use std::convert::Infallible;
 
 use thiserror::Error as ThisError;
 
-#[derive(ThisError, Eq, PartialEq, Debug)]
+use crate::ParseQualityError;
+
+#[derive(ThisError, Clone, Eq, PartialEq, Debug)]
 pub enum Error {
     #[error("missing separator \"{0}\"")]
     MissingSeparator(char),
@@ -17,5 +19,31 @@ pub enum Error {
     #[error("quality param not allowed")]
     QualityNotAllowed,
     #[error("invalid quality param")]
-    InvalidQuality { source: <f32 as FromStr>::Err },
+    InvalidQuality { source: ParseQualityError },
+    #[error("not acceptable")]
+    NotAcceptable,
+    #[error("weight must be between 0 and 1")]
+    InvalidWeight,
+    #[error("empty supported list")]
+    EmptySupported,
+    #[error("missing signature")]
+    MissingSignature,
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("duplicate supported value")]
+    DuplicateSupported,
+    #[error("header exceeds parsing limits")]
+    LimitExceeded,
+    #[error("unknown parameter")]
+    UnknownParameter,
+    #[error("invalid language tag")]
+    InvalidLanguageTag,
+    #[error("invalid mime")]
+    InvalidMime,
+}
+
+impl From<Infallible> for Error {
+    fn from(infallible: Infallible) -> Self {
+        match infallible {}
+    }
 }
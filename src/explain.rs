@@ -0,0 +1,174 @@
+use crate::{sort_ranges, Error, NegotiationType, Negotiator, Quality};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Explanation {
+    pub ranges: Vec<ExplainedRange>,
+    pub candidates: Vec<Candidate>,
+    pub selected: Option<String>,
+}
+
+/// A header range, in the order the client prefers them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ExplainedRange {
+    pub range: String,
+    pub quality: Quality,
+}
+
+/// A supported value, in the order the negotiator lists them, along with the indices of the
+/// header ranges matching it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Candidate {
+    pub value: String,
+    pub matched: Vec<usize>,
+    pub outcome: Outcome,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Outcome {
+    /// Position in the final ranking (0 being the selected value), with the index of the range
+    /// that scored it and the resulting quality.
+    Ranked {
+        rank: usize,
+        range: usize,
+        quality: Quality,
+    },
+    Disabled,
+    Unavailable,
+    NoMatch,
+    /// The most specific matching range has a quality of 0.
+    Excluded {
+        range: usize,
+    },
+}
+
+impl<N: NegotiationType, T> Negotiator<N, T> {
+    /// Negotiates while recording how every header range and supported value was considered.
+    pub fn explain(&self, header: &str) -> Result<Explanation, Error> {
+        let ranges = sort_ranges::<N>(self.parse_header(header)?);
+        let ranked = self.rank(&ranges);
+        let now = self.now();
+        let candidates = self
+            .supported
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let matched = ranges
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (r, _q))| self.range_matches(&entry.parsed, r))
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>();
+                let outcome = if let Some((rank, ranked)) = ranked
+                    .iter()
+                    .enumerate()
+                    .find(|(_, ranked)| ranked.index == index)
+                {
+                    Outcome::Ranked {
+                        rank,
+                        range: ranked.range,
+                        quality: ranked.quality,
+                    }
+                } else if entry.weight == Quality::ZERO {
+                    Outcome::Disabled
+                } else if now.is_some_and(|now| !entry.availability.contains(now)) {
+                    Outcome::Unavailable
+                } else {
+                    match matched
+                        .iter()
+                        .rev()
+                        .max_by_key(|i| N::precedence(&ranges[**i].0))
+                    {
+                        Some(range) => Outcome::Excluded { range: *range },
+                        None => Outcome::NoMatch,
+                    }
+                };
+                Candidate {
+                    value: entry.source.clone(),
+                    matched,
+                    outcome,
+                }
+            })
+            .collect();
+        Ok(Explanation {
+            ranges: ranges
+                .iter()
+                .map(|(r, q)| ExplainedRange {
+                    range: N::format_range(r),
+                    quality: *q,
+                })
+                .collect(),
+            candidates,
+            selected: ranked.first().map(|ranked| ranked.entry.source.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExplainedRange, Outcome};
+    use crate::{ContentTypeNegotiation, Negotiator, Quality};
+
+    #[test]
+    fn explain() {
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::new_weighted([
+            ("application/json", 1.),
+            ("text/plain", 1.),
+            ("text/html", 1.),
+            ("text/csv", 0.),
+            ("image/png", 1.),
+        ])
+        .unwrap();
+        let explanation = negotiator
+            .explain("text/*;q=0.8, application/json;q=0.5, text/html;q=0")
+            .unwrap();
+
+        assert_eq!(
+            explanation.ranges,
+            vec![
+                ExplainedRange {
+                    range: "text/*".to_owned(),
+                    quality: Quality::from_thousandths(800).unwrap()
+                },
+                ExplainedRange {
+                    range: "application/json".to_owned(),
+                    quality: Quality::from_thousandths(500).unwrap()
+                },
+                ExplainedRange {
+                    range: "text/html".to_owned(),
+                    quality: Quality::ZERO
+                },
+            ]
+        );
+        assert_eq!(explanation.selected.as_deref(), Some("text/plain"));
+        assert_eq!(
+            explanation
+                .candidates
+                .iter()
+                .map(|c| (c.value.as_str(), c.matched.clone(), c.outcome))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "application/json",
+                    vec![1],
+                    Outcome::Ranked {
+                        rank: 1,
+                        range: 1,
+                        quality: Quality::from_thousandths(500).unwrap()
+                    }
+                ),
+                (
+                    "text/plain",
+                    vec![0],
+                    Outcome::Ranked {
+                        rank: 0,
+                        range: 0,
+                        quality: Quality::from_thousandths(800).unwrap()
+                    }
+                ),
+                ("text/html", vec![0, 2], Outcome::Excluded { range: 2 }),
+                ("text/csv", vec![0], Outcome::Disabled),
+                ("image/png", vec![], Outcome::NoMatch),
+            ]
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use fluent_bundle::bundle::FluentBundle;
+use unic_langid::LanguageIdentifier;
+
+use crate::{Error, LanguageNegotiation, Negotiator};
+
+impl Negotiator<LanguageNegotiation, LanguageIdentifier> {
+    /// Builds a negotiator supporting [`LanguageIdentifier`] values, returned as is when selected.
+    pub fn from_language_identifiers<I>(iter: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = LanguageIdentifier>,
+    {
+        Ok(Negotiator::<LanguageNegotiation, String>::new(
+            iter.into_iter().map(|id| id.to_string()),
+        )?
+        .map(|value| {
+            value
+                .parse()
+                .expect("supported values are language identifiers")
+        }))
+    }
+}
+
+/// Selects the Fluent bundle matching an `Accept-Language` header, each bundle being supported
+/// under its first locale. The first bundle serves the requests matching none of them.
+pub struct BundleNegotiator<R, M> {
+    negotiator: Negotiator<LanguageNegotiation, LanguageIdentifier>,
+    bundles: Vec<FluentBundle<R, M>>,
+}
+
+impl<R, M> BundleNegotiator<R, M> {
+    pub fn new<I>(bundles: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = FluentBundle<R, M>>,
+    {
+        let bundles = bundles.into_iter().collect::<Vec<_>>();
+        let negotiator = Negotiator::from_language_identifiers(
+            bundles
+                .iter()
+                .map(|bundle| bundle.locales.first().cloned())
+                .collect::<Option<Vec<_>>>()
+                .ok_or(Error::InvalidLanguageTag)?,
+        )?;
+        if negotiator.is_empty() {
+            return Err(Error::EmptySupported);
+        }
+        Ok(Self {
+            negotiator,
+            bundles,
+        })
+    }
+
+    pub fn negotiator(&self) -> &Negotiator<LanguageNegotiation, LanguageIdentifier> {
+        &self.negotiator
+    }
+
+    pub fn bundles(&self) -> &[FluentBundle<R, M>] {
+        &self.bundles
+    }
+
+    pub fn negotiate(&self, header: &str) -> Result<Option<&FluentBundle<R, M>>, Error> {
+        Ok(self.negotiator.negotiate(header)?.map(|id| self.bundle(id)))
+    }
+
+    /// Negotiates against an optional header, falling back on the first bundle when the header
+    /// is missing, malformed or matches none of them.
+    pub fn negotiate_or_first(&self, header: Option<&str>) -> &FluentBundle<R, M> {
+        let id = header
+            .and_then(|header| self.negotiator.negotiate(header).ok().flatten())
+            .unwrap_or_else(|| self.negotiator.unwrap_first());
+        self.bundle(id)
+    }
+
+    fn bundle(&self, id: &LanguageIdentifier) -> &FluentBundle<R, M> {
+        self.bundles
+            .iter()
+            .find(|bundle| bundle.locales.first() == Some(id))
+            .expect("negotiated locales come from the bundles")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fluent_bundle::{FluentBundle, FluentResource};
+    use unic_langid::LanguageIdentifier;
+
+    use super::BundleNegotiator;
+    use crate::{Error, LanguageNegotiation, Negotiator};
+
+    fn bundle(locale: &str, hello: &str) -> FluentBundle<FluentResource> {
+        let mut bundle = FluentBundle::new(vec![locale.parse().unwrap()]);
+        bundle
+            .add_resource(FluentResource::try_new(format!("hello = {hello}")).unwrap())
+            .unwrap();
+        bundle
+    }
+
+    fn hello(bundle: &FluentBundle<FluentResource>) -> String {
+        let pattern = bundle.get_message("hello").unwrap().value().unwrap();
+        bundle
+            .format_pattern(pattern, None, &mut Vec::new())
+            .into_owned()
+    }
+
+    #[test]
+    fn negotiate() {
+        let negotiator =
+            BundleNegotiator::new([bundle("en-US", "Hello"), bundle("fr-FR", "Bonjour")]).unwrap();
+        assert_eq!(
+            negotiator.negotiate("fr-CH, fr;q=0.9").unwrap().map(hello),
+            Some("Bonjour".to_owned())
+        );
+        assert!(negotiator.negotiate("de").unwrap().is_none());
+        assert_eq!(hello(negotiator.negotiate_or_first(Some("de"))), "Hello");
+        assert_eq!(hello(negotiator.negotiate_or_first(None)), "Hello");
+
+        assert_eq!(
+            BundleNegotiator::new([FluentBundle::<FluentResource>::new(Vec::new())]).err(),
+            Some(Error::InvalidLanguageTag)
+        );
+        assert_eq!(
+            BundleNegotiator::new(Vec::<FluentBundle<FluentResource>>::new()).err(),
+            Some(Error::EmptySupported)
+        );
+    }
+
+    #[test]
+    fn from_language_identifiers() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::from_language_identifiers([
+            "en-US".parse::<LanguageIdentifier>().unwrap(),
+            "zh-Hant".parse().unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(
+            negotiator.negotiate("zh-Hant-TW, zh;q=0.5").unwrap(),
+            Some(&"zh-Hant".parse().unwrap())
+        );
+    }
+}
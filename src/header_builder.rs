@@ -0,0 +1,146 @@
+use std::marker::PhantomData;
+
+use crate::{params::split_unquoted, Error, HeaderParams, NegotiationType, Quality};
+
+const DEFAULT_STEP: Quality = Quality::from_thousandths(100).unwrap();
+const LOWEST: Quality = Quality::from_thousandths(1).unwrap();
+
+/// Builds a header for the request side from an ordered preference list, e.g.
+/// `application/json, text/html;q=0.9, */*;q=0.1`.
+///
+/// Values added with [`HeaderBuilder::prefer`] get descending qualities, starting at 1 and
+/// decreasing by the step (0.1 by default) without ever reaching 0.
+#[derive(Clone, Debug)]
+pub struct HeaderBuilder<N> {
+    elements: Vec<(String, Option<Quality>)>,
+    step: Quality,
+    _negotiation: PhantomData<N>,
+}
+
+impl<N: NegotiationType> Default for HeaderBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: NegotiationType> HeaderBuilder<N> {
+    pub fn new() -> Self {
+        Self {
+            elements: Vec::new(),
+            step: DEFAULT_STEP,
+            _negotiation: PhantomData,
+        }
+    }
+
+    pub fn prefer(mut self, value: &str) -> Self {
+        self.elements.push((value.to_owned(), None));
+        self
+    }
+
+    /// Adds a value with an explicit quality, e.g. a `*/*` fallback, not affecting the qualities
+    /// of the preferred values.
+    pub fn with_quality(mut self, value: &str, quality: Quality) -> Self {
+        self.elements.push((value.to_owned(), Some(quality)));
+        self
+    }
+
+    pub fn step(mut self, step: Quality) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Validates every value against the header grammar, which must not carry a quality.
+    pub fn build(self) -> Result<String, Error> {
+        let mut next = Quality::ONE;
+        let mut elements = Vec::with_capacity(self.elements.len());
+        for (value, quality) in self.elements {
+            let value = value.trim();
+            if split_unquoted(value, ',').nth(1).is_some() {
+                return Err(Error::InvalidHeader);
+            }
+            let range = split_unquoted(value, ';').next().unwrap_or_default();
+            if HeaderParams::extensions(&value[range.len()..])
+                .iter()
+                .any(|(k, _)| k.trim().eq_ignore_ascii_case("q"))
+            {
+                return Err(Error::QualityNotAllowed);
+            }
+            N::parse_header_elem(value)?;
+            let quality = quality.unwrap_or_else(|| {
+                let quality = next;
+                next = next.saturating_sub(self.step).max(LOWEST);
+                quality
+            });
+            elements.push(match quality {
+                Quality::ONE => value.to_owned(),
+                q => format!("{value};q={q}"),
+            });
+        }
+        Ok(elements.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeaderBuilder;
+    use crate::{ContentTypeNegotiation, Error, LanguageNegotiation, Quality};
+
+    #[test]
+    fn build() {
+        assert_eq!(
+            HeaderBuilder::<ContentTypeNegotiation>::new()
+                .prefer("application/json")
+                .prefer("text/html")
+                .with_quality("*/*", Quality::from_thousandths(100).unwrap())
+                .build(),
+            Ok("application/json, text/html;q=0.9, */*;q=0.1".to_owned())
+        );
+        assert_eq!(
+            HeaderBuilder::<LanguageNegotiation>::new()
+                .step(Quality::from_thousandths(400).unwrap())
+                .prefer("fr-CH")
+                .prefer("fr")
+                .prefer("en")
+                .prefer("de")
+                .build(),
+            Ok("fr-CH, fr;q=0.6, en;q=0.2, de;q=0.001".to_owned())
+        );
+        assert_eq!(
+            HeaderBuilder::<ContentTypeNegotiation>::new().build(),
+            Ok(String::new())
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            HeaderBuilder::<ContentTypeNegotiation>::new()
+                .prefer("text/html;q=0.5")
+                .build(),
+            Err(Error::QualityNotAllowed)
+        );
+        assert_eq!(
+            HeaderBuilder::<ContentTypeNegotiation>::new()
+                .prefer("text/html, text/plain")
+                .build(),
+            Err(Error::InvalidHeader)
+        );
+        assert_eq!(
+            HeaderBuilder::<ContentTypeNegotiation>::new()
+                .prefer("*/html")
+                .build(),
+            Err(Error::InvalidWildcard)
+        );
+    }
+
+    #[test]
+    fn quoted() {
+        assert_eq!(
+            HeaderBuilder::<ContentTypeNegotiation>::new()
+                .prefer(r#"text/html;title="a,b""#)
+                .prefer(r#"text/plain;title="x;q=2""#)
+                .build(),
+            Ok(r#"text/html;title="a,b", text/plain;title="x;q=2";q=0.9"#.to_owned())
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use ::icu_locale::{LanguageIdentifier, LocaleExpander};
+
+use crate::{
+    language::range_subtags, sort_ranges, Error, LanguageNegotiation, LanguageTag, NegotiationType,
+    Negotiator, Quality,
+};
+
+// Distances from a desired language to a distinct supported one close enough to serve it, from
+// CLDR's language matching data.
+const LANGUAGE_DISTANCES: &[(&str, &str, u16)] = &[
+    ("no", "nb", 1),
+    ("nb", "no", 1),
+    ("nn", "nb", 20),
+    ("nn", "no", 20),
+    ("bs", "hr", 4),
+    ("hr", "bs", 4),
+];
+
+// CLDR splits the regional variants of English, Spanish and Portuguese in two groups, a variant
+// being closer to the ones of its group, e.g. `en-AU` to `en-GB` rather than `en-US`.
+const EN_US: &[&str] = &["AS", "CA", "GU", "MH", "MP", "PH", "PR", "UM", "US", "VI"];
+const AMERICAS: &[&str] = &[
+    "019", "419", "AR", "BO", "BR", "CA", "CL", "CO", "CR", "CU", "DO", "EC", "GT", "HN", "MX",
+    "NI", "PA", "PE", "PR", "PY", "SV", "US", "UY", "VE",
+];
+
+impl<T> Negotiator<LanguageNegotiation, T> {
+    /// Negotiates with CLDR's likely subtags and language matching data, suited to picking a
+    /// single language: ranges are tried in quality order and each one selects the closest
+    /// supported tag once both are maximized (e.g. `en-AU` to `en-Latn-AU`), ties going to the
+    /// first one. This lets `no` match `nb` and `en-AU` prefer `en-GB` over `en-US`. Ranges with a
+    /// `*` subtag are ignored and a tag matched by a `q=0` range is never selected. Among ranges of
+    /// the same quality, the supported order wins with
+    /// [`TieBreak::ServerOrder`](crate::TieBreak::ServerOrder). When no range selects a tag, the
+    /// explicit default is returned.
+    pub fn negotiate_closest(&self, header: &str) -> Result<Option<&T>, Error> {
+        let ranges = sort_ranges::<LanguageNegotiation>(self.parse_header(header)?);
+        let expander = LocaleExpander::new_extended();
+        let now = self.now();
+        let supported = self
+            .supported
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                entry.is_selectable(now)
+                    && !ranges.iter().any(|(r, q)| {
+                        *q == Quality::ZERO && LanguageNegotiation::matches(&entry.parsed, r)
+                    })
+            })
+            .filter_map(|(i, entry)| Some((i, maximize(&expander, &entry.parsed)?)))
+            .collect::<Vec<_>>();
+        self.select_by_range(&ranges, |range| {
+            if range_subtags(range).any(|s| s == "*") {
+                return None;
+            }
+            let desired = LanguageTag::parse(&LanguageNegotiation::format_range(range))
+                .ok()
+                .and_then(|tag| maximize(&expander, &tag))?;
+            supported
+                .iter()
+                .filter_map(|(i, id)| Some((*i, distance(&desired, id)?)))
+                .min_by_key(|(_, distance)| *distance)
+                .map(|(i, _)| i)
+        })
+    }
+}
+
+// An extended language subtag is the language itself, e.g. `yue` for `zh-yue`.
+fn maximize(expander: &LocaleExpander, tag: &LanguageTag) -> Option<LanguageIdentifier> {
+    let language = tag.extlangs().next().unwrap_or(tag.primary());
+    let id = [Some(language), tag.script(), tag.region()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("-");
+    let mut id = LanguageIdentifier::try_from_str(&id).ok()?;
+    expander.maximize(&mut id);
+    Some(id)
+}
+
+// Different scripts are too far apart to be served one for the other.
+fn distance(desired: &LanguageIdentifier, supported: &LanguageIdentifier) -> Option<u16> {
+    if desired.script != supported.script {
+        return None;
+    }
+    let language = match desired.language == supported.language {
+        true => 0,
+        false => {
+            LANGUAGE_DISTANCES
+                .iter()
+                .find(|(d, s, _)| {
+                    desired.language.as_str() == *d && supported.language.as_str() == *s
+                })?
+                .2
+        }
+    };
+    let region = match (desired.region, supported.region) {
+        (d, s) if d == s => 0,
+        (Some(d), Some(s)) if same_group(desired.language.as_str(), d.as_str(), s.as_str()) => 4,
+        _ => 5,
+    };
+    Some(language + region)
+}
+
+fn same_group(language: &str, r1: &str, r2: &str) -> bool {
+    let group = match language {
+        "en" => EN_US,
+        "es" | "pt" => AMERICAS,
+        _ => return true,
+    };
+    group.contains(&r1) == group.contains(&r2)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use crate::{Availability, Error, LanguageNegotiation, Negotiator};
+
+    #[test]
+    fn negotiate_closest() {
+        let negotiator = |supported: &[&'static str]| {
+            Negotiator::<LanguageNegotiation, _>::new(supported.iter().copied()).unwrap()
+        };
+        let closest = |supported: &[&'static str], header| {
+            negotiator(supported)
+                .negotiate_closest(header)
+                .unwrap()
+                .copied()
+        };
+        assert_eq!(closest(&["en", "nb"], "no"), Some("nb"));
+        assert_eq!(closest(&["en-US", "en-GB"], "en-AU"), Some("en-GB"));
+        assert_eq!(closest(&["en-US", "en-GB"], "en-CA"), Some("en-US"));
+        assert_eq!(closest(&["en-GB", "en-US"], "en"), Some("en-US"));
+        assert_eq!(closest(&["pt-BR", "pt-PT"], "pt-AO"), Some("pt-PT"));
+        assert_eq!(closest(&["zh-Hans", "zh-Hant"], "zh-TW"), Some("zh-Hant"));
+        assert_eq!(closest(&["sr-Cyrl"], "sr-Latn"), None);
+        assert_eq!(closest(&["de-DE", "fr-CA"], "de;q=0.5, fr"), Some("fr-CA"));
+        assert_eq!(
+            closest(&["en-US", "en-GB"], "en-AU, en-GB;q=0"),
+            Some("en-US")
+        );
+        assert_eq!(closest(&["en-US"], "*, fr"), None);
+
+        let negotiator = negotiator(&["en-US", "en-GB"])
+            .with_availability(
+                "en-GB",
+                Availability::from(SystemTime::now() + Duration::from_secs(3600)),
+            )
+            .with_default("fr");
+        assert_eq!(
+            negotiator.negotiate_closest("en-AU").unwrap(),
+            Some(&"en-US")
+        );
+        assert_eq!(negotiator.negotiate_closest("de").unwrap(), Some(&"fr"));
+
+        assert_eq!(
+            Negotiator::<LanguageNegotiation, _>::builder()
+                .supported("en-US")
+                .strict(true)
+                .build()
+                .unwrap()
+                .negotiate_closest("fr"),
+            Err(Error::NotAcceptable)
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+    sync::OnceLock,
+    vec,
+};
+
+use crate::{Entry, NegotiationType, Quality};
+
+/// The supported entries along with an index of the ones matched through a plain key, built on
+/// first use and dropped whenever the entries are borrowed mutably.
+#[derive(Debug)]
+pub(crate) struct Supported<P, T> {
+    entries: Vec<Entry<P, T>>,
+    index: OnceLock<ExactIndex>,
+}
+
+#[derive(Debug)]
+struct ExactIndex {
+    keyed: HashMap<String, Vec<usize>>,
+    unkeyed: Vec<usize>,
+}
+
+impl<P, T> Supported<P, T> {
+    /// The positions of the entries which may match one of the ranges, in supported order, or
+    /// `None` when a range has no key and every entry must be considered.
+    pub(crate) fn candidates<N>(&self, ranges: &[(N::Range<'_>, Quality)]) -> Option<Vec<usize>>
+    where
+        N: NegotiationType<Parsed = P>,
+    {
+        let keys = ranges
+            .iter()
+            .map(|(range, _q)| N::range_key(range))
+            .collect::<Option<Vec<_>>>()?;
+        let index = self.index.get_or_init(|| {
+            let mut index = ExactIndex {
+                keyed: HashMap::new(),
+                unkeyed: Vec::new(),
+            };
+            for (i, entry) in self.entries.iter().enumerate() {
+                match N::supported_key(&entry.parsed) {
+                    Some(key) => index.keyed.entry(key).or_default().push(i),
+                    None => index.unkeyed.push(i),
+                }
+            }
+            index
+        });
+        let mut candidates = index.unkeyed.clone();
+        for key in &keys {
+            if let Some(keyed) = index.keyed.get(key) {
+                candidates.extend(keyed);
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        Some(candidates)
+    }
+}
+
+impl<P: Clone, T: Clone> Clone for Supported<P, T> {
+    fn clone(&self) -> Self {
+        self.entries.clone().into()
+    }
+}
+
+impl<P, T> From<Vec<Entry<P, T>>> for Supported<P, T> {
+    fn from(entries: Vec<Entry<P, T>>) -> Self {
+        Self {
+            entries,
+            index: OnceLock::new(),
+        }
+    }
+}
+
+impl<P, T> Deref for Supported<P, T> {
+    type Target = Vec<Entry<P, T>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl<P, T> DerefMut for Supported<P, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.index.take();
+        &mut self.entries
+    }
+}
+
+impl<P, T> IntoIterator for Supported<P, T> {
+    type Item = Entry<P, T>;
+    type IntoIter = vec::IntoIter<Entry<P, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<P: PartialEq, T: PartialEq> PartialEq<Vec<Entry<P, T>>> for Supported<P, T> {
+    fn eq(&self, other: &Vec<Entry<P, T>>) -> bool {
+        self.entries == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ContentTypeNegotiation, NegotiationType, Negotiator};
+
+    #[test]
+    fn candidates() {
+        let mut negotiator = Negotiator::<ContentTypeNegotiation, _>::new([
+            "application/json",
+            "text/html;level=1",
+            "text/html",
+            "text/plain",
+        ])
+        .unwrap();
+        let ranges = |header| {
+            ContentTypeNegotiation::parse_header_elem(header)
+                .map(|range| vec![range])
+                .unwrap()
+        };
+        assert_eq!(
+            negotiator
+                .supported
+                .candidates::<ContentTypeNegotiation>(&ranges("Text/HTML")),
+            Some(vec![1, 2])
+        );
+        assert_eq!(
+            negotiator
+                .supported
+                .candidates::<ContentTypeNegotiation>(&ranges("text/*")),
+            None
+        );
+        assert_eq!(
+            negotiator
+                .supported
+                .candidates::<ContentTypeNegotiation>(&ranges("text/html;level=1")),
+            None
+        );
+
+        negotiator.push("application/xml").unwrap();
+        assert_eq!(
+            negotiator
+                .supported
+                .candidates::<ContentTypeNegotiation>(&ranges("application/xml")),
+            Some(vec![1, 4])
+        );
+        assert_eq!(
+            negotiator
+                .negotiate("application/xml, text/html;q=0.5")
+                .unwrap(),
+            Some(&"application/xml")
+        );
+    }
+}
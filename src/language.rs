@@ -1,87 +1,289 @@
-use crate::{match_first, AsNegotiationStr, Error, MaybeWildcard, NegotiationType};
+use std::iter;
 
+use crate::{
+    language_tag::{modern_language, preferred, subtags_eq},
+    sort_ranges, AsNegotiationStr, Error, LanguageTag, MaybeWildcard, NegotiationKind,
+    NegotiationType, Negotiator, Quality, TieBreak,
+};
+
+type LanguageRange<'a> = <LanguageNegotiation as NegotiationType>::Range<'a>;
+
+/// Negotiates BCP 47 language tags, e.g. `en-GB` or `zh-Hant-TW`, see [`LanguageTag`].
+///
+/// Grandfathered tags, e.g. `i-klingon`, are replaced by their preferred value, e.g. `tlh`, and
+/// withdrawn language codes by their modern one, e.g. `iw` by `he`, both in supported values and
+/// header ranges.
+///
+/// Header ranges follow RFC 4647 extended filtering: a `*` subtag in any position matches any
+/// sequence of subtags, e.g. `de-*-DE` matches `de-Latn-DE` and `*-CH` matches `fr-CH`.
+///
+/// A supported tag without region, e.g. `en`, serves any range of its language, regioned or not.
+/// It ties with a supported regioned tag matching the same range, which it only loses to with
+/// [`TieBreak::MostSpecific`](crate::TieBreak::MostSpecific) or when listed after it.
 #[derive(Copy, Clone, Debug)]
 pub struct LanguageNegotiation;
 
 impl NegotiationType for LanguageNegotiation {
-    type Parsed = (String, String);
+    type Parsed = LanguageTag;
+    type Range<'a> = (&'a str, MaybeWildcard<&'a str>);
+
+    const KIND: NegotiationKind = NegotiationKind::Language;
 
     fn parse_elem<M: AsNegotiationStr>(input: &M) -> Result<Self::Parsed, Error> {
         let input = input.as_str();
         if input.contains(';') {
             return Err(Error::ParamsNotAllowed);
         }
-        input
-            .split_once('-')
-            .ok_or(Error::MissingSeparator('-'))
-            .map(|(main, sub)| (main.to_owned(), sub.to_owned()))
-    }
-
-    fn parse_and_negotiate_header<'a, T>(
-        supported: &'a [(Self::Parsed, T)],
-        header: &str,
-    ) -> Result<Option<&'a T>, Error> {
-        let mut languages = header
-            .split(',')
-            .map(|entry| {
-                let mut parts = entry.split(';').map(str::trim);
-                let left = parts.next().ok_or(Error::InvalidHeader)?;
-                let (main, sub) = left
-                    .split_once('-')
-                    .map(|(m, s)| (m, MaybeWildcard::Specific(s)))
-                    .unwrap_or((left, MaybeWildcard::Wildcard));
-                let q = match parts.next() {
-                    Some(first_param) => {
-                        let (k, v) = first_param.split_once('=').ok_or(Error::InvalidHeader)?;
-                        if k != "q" || parts.next().is_some() {
-                            return Err(Error::ParamsNotAllowed);
-                        }
-                        v.parse::<f32>()
-                            .map_err(|err| Error::InvalidQuality { source: err })?
-                    }
-                    None => 1.,
-                };
-                Ok(((main, sub), q))
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        languages.sort_by(|((_, s1), q1), ((_, s2), q2)| {
-            q1.total_cmp(q2)
-                .then_with(|| {
-                    (matches!(s2, MaybeWildcard::Wildcard))
-                        .cmp(&(matches!(s1, MaybeWildcard::Wildcard)))
-                })
-                .reverse()
-        });
-        Ok(match_first(
-            supported,
-            languages.iter().map(|(l, _q)| l),
-            |s, h| s.0 == h.0 && h.1.matches(&s.1),
-        ))
-    }
-
-    #[cfg(feature = "axum")]
+        LanguageTag::parse(input)
+    }
+
+    fn parse_header_elem(input: &str) -> Result<(Self::Range<'_>, Quality), Error> {
+        parse_range(input, false)
+    }
+
+    fn parse_quirky_header_elem(input: &str) -> Result<(Self::Range<'_>, Quality), Error> {
+        parse_range(input, true)
+    }
+
+    fn empty_header<'a>() -> Option<(Self::Range<'a>, Quality)> {
+        Some((("*", MaybeWildcard::Wildcard), Quality::ONE))
+    }
+
+    // The more subtags a range pins down, the more specific it is.
+    fn precedence(range: &Self::Range<'_>) -> (u8, usize) {
+        let specific = range_subtags(range).filter(|s| *s != "*").count();
+        (specific.try_into().unwrap_or(u8::MAX), 0)
+    }
+
+    fn specificity(supported: &Self::Parsed) -> usize {
+        supported.subtags().count() - 1
+    }
+
+    fn matches(supported: &Self::Parsed, range: &Self::Range<'_>) -> bool {
+        extended_filter(range_subtags(range), supported.subtags())
+            || (supported.is_language_only() && supported.primary().eq_ignore_ascii_case(range.0))
+    }
+
+    fn is_wildcard(range: &Self::Range<'_>) -> bool {
+        range.0 == "*" && range.1 == MaybeWildcard::Wildcard
+    }
+
+    fn format_range(range: &Self::Range<'_>) -> String {
+        match range.1 {
+            MaybeWildcard::Specific(sub) => format!("{}-{sub}", range.0),
+            MaybeWildcard::Wildcard => range.0.to_owned(),
+        }
+    }
+
+    #[cfg(feature = "http")]
     fn associated_header() -> http::header::HeaderName {
         http::header::ACCEPT_LANGUAGE
     }
 }
 
+impl<T> Negotiator<LanguageNegotiation, T> {
+    /// Negotiates with the RFC 4647 lookup scheme, suited to picking a single language: ranges
+    /// are tried in quality order and each one is progressively truncated (`zh-Hant-CN`, then
+    /// `zh-Hant`, then `zh`) until it equals a supported tag. Ranges with a `*` subtag are
+    /// ignored and a tag listed with `q=0` is never selected. Among ranges of the same quality,
+    /// the supported order wins with [`TieBreak::ServerOrder`]. When no range selects a tag, the
+    /// explicit default is returned, as the last step of the lookup.
+    pub fn negotiate_lookup(&self, header: &str) -> Result<Option<&T>, Error> {
+        let ranges = sort_ranges::<LanguageNegotiation>(self.parse_header(header)?);
+        let now = self.now();
+        let excluded = |main: &str, rest: Option<&str>| {
+            ranges
+                .iter()
+                .any(|(r, q)| *q == Quality::ZERO && tag_eq(r, main, rest))
+        };
+        self.select_by_range(&ranges, |range| {
+            if range_subtags(range).any(|s| s == "*") {
+                return None;
+            }
+            let mut rest = match range.1 {
+                MaybeWildcard::Specific(rest) => Some(rest),
+                MaybeWildcard::Wildcard => None,
+            };
+            loop {
+                let selected = self.supported.iter().position(|entry| {
+                    entry.is_selectable(now)
+                        && subtags_eq(
+                            entry.parsed.subtags(),
+                            iter::once(range.0).chain(rest.into_iter().flat_map(|r| r.split('-'))),
+                        )
+                });
+                if selected.is_some() && !excluded(range.0, rest) {
+                    return selected;
+                }
+                rest = truncate(rest?);
+            }
+        })
+    }
+
+    // Tries the ranges by decreasing quality, `select` giving the index of the supported entry
+    // a range selects. Among ranges of the same quality, the first one selecting an entry wins,
+    // or the entry listed first with `TieBreak::ServerOrder`. The explicit default is selected
+    // when no range selects an entry, unless a q=0 range excludes it.
+    pub(crate) fn select_by_range(
+        &self,
+        ranges: &[(LanguageRange<'_>, Quality)],
+        mut select: impl FnMut(&LanguageRange<'_>) -> Option<usize>,
+    ) -> Result<Option<&T>, Error> {
+        for group in ranges
+            .chunk_by(|(_, q1), (_, q2)| q1 == q2)
+            .filter(|group| group[0].1 > Quality::ZERO)
+        {
+            let mut selected = group.iter().filter_map(|(range, _)| select(range));
+            let selected = match self.options.tie_break {
+                TieBreak::ServerOrder => selected.min(),
+                _ => selected.next(),
+            };
+            if let Some(i) = selected {
+                return Ok(Some(&self.supported[i].value));
+            }
+        }
+        if let Some(default) = self.default.as_deref().filter(|default| {
+            default
+                .parsed
+                .as_ref()
+                .is_none_or(|parsed| !self.is_excluded(ranges, parsed))
+        }) {
+            return Ok(Some(&default.value));
+        }
+        if self.options.strict {
+            return Err(Error::NotAcceptable);
+        }
+        Ok(None)
+    }
+}
+
+/// Builds a `Content-Language` value from the languages of a response, e.g. the negotiated one or
+/// every language of a multilingual document. Each value must be a language tag, repeated tags
+/// are only listed once and at least one tag is required.
+#[cfg(feature = "http")]
+pub fn content_language<I>(languages: I) -> Result<http::HeaderValue, Error>
+where
+    I: IntoIterator,
+    I::Item: AsNegotiationStr,
+{
+    let mut tags = Vec::<(LanguageTag, String)>::new();
+    for language in languages {
+        let language = language.as_str().trim();
+        let tag = LanguageNegotiation::parse_elem(&language)?;
+        if tags.iter().all(|(t, _)| *t != tag) {
+            tags.push((tag, language.to_owned()));
+        }
+    }
+    if tags.is_empty() {
+        return Err(Error::InvalidHeader);
+    }
+    let value = tags
+        .into_iter()
+        .map(|(_, language)| language)
+        .collect::<Vec<_>>()
+        .join(", ");
+    http::HeaderValue::from_str(&value).map_err(|_| Error::InvalidHeader)
+}
+
+fn tag_eq(
+    range: &<LanguageNegotiation as NegotiationType>::Range<'_>,
+    main: &str,
+    rest: Option<&str>,
+) -> bool {
+    range.0.eq_ignore_ascii_case(main)
+        && match range.1 {
+            MaybeWildcard::Specific(r) => rest.is_some_and(|rest| r.eq_ignore_ascii_case(rest)),
+            MaybeWildcard::Wildcard => rest.is_none(),
+        }
+}
+
+// Drops the last subtag of the part following the primary language, along with the singleton it
+// may follow, e.g. `Hant-CN-x` once `private1` is dropped from `Hant-CN-x-private1`.
+fn truncate(rest: &str) -> Option<&str> {
+    let (rest, _) = rest.rsplit_once('-')?;
+    match rest.rsplit_once('-') {
+        Some((head, last)) if last.len() == 1 => Some(head),
+        None if rest.len() == 1 => None,
+        _ => Some(rest),
+    }
+}
+
+pub(crate) fn range_subtags<'a>(
+    range: &<LanguageNegotiation as NegotiationType>::Range<'a>,
+) -> impl Iterator<Item = &'a str> {
+    let rest = match range.1 {
+        MaybeWildcard::Specific(rest) => Some(rest),
+        MaybeWildcard::Wildcard => None,
+    };
+    iter::once(range.0).chain(rest.into_iter().flat_map(|rest| rest.split('-')))
+}
+
+// RFC 4647 section 3.3.2: the first subtags must match, then every range subtag other than `*`
+// must be found in order in the tag, without skipping over a singleton. Subtags ignore case.
+fn extended_filter<'a, 'b>(
+    mut range: impl Iterator<Item = &'a str>,
+    mut tag: impl Iterator<Item = &'b str>,
+) -> bool {
+    match (range.next(), tag.next()) {
+        (Some(r), Some(t)) if r == "*" || r.eq_ignore_ascii_case(t) => {}
+        _ => return false,
+    }
+    for r in range.filter(|r| *r != "*") {
+        loop {
+            match tag.next() {
+                Some(t) if t.eq_ignore_ascii_case(r) => break,
+                Some(t) if t.len() > 1 => {}
+                _ => return false,
+            }
+        }
+    }
+    true
+}
+
+fn parse_range(
+    input: &str,
+    quirks: bool,
+) -> Result<(<LanguageNegotiation as NegotiationType>::Range<'_>, Quality), Error> {
+    let (left, q) = Quality::split_weight(input, quirks)?;
+    // The whole tag is a wildcard, rather than a `*` language with any other subtag.
+    if left == "*" {
+        return Ok((("*", MaybeWildcard::Wildcard), q));
+    }
+    let left = preferred(left);
+    let (main, sub) = left
+        .split_once('-')
+        .map(|(m, s)| (m, MaybeWildcard::Specific(s)))
+        .unwrap_or((left, MaybeWildcard::Wildcard));
+    Ok(((modern_language(main), sub), q))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::LanguageNegotiation;
-    use crate::{Error, Negotiator};
+    use std::{
+        borrow::Cow,
+        time::{Duration, SystemTime},
+    };
+
+    use super::{extended_filter, truncate, LanguageNegotiation};
+    use crate::{
+        Availability, Entry, Error, LanguageTag, Negotiator, ParseQualityError, Quality, TieBreak,
+    };
+    use crate::{MaybeWildcard, NegotiationType};
 
     #[test]
     fn new() {
         assert_eq!(
-            Negotiator::<LanguageNegotiation, _>::new(["en-US"])
+            *Negotiator::<LanguageNegotiation, _>::new(["en-US"])
                 .unwrap()
                 .supported,
-            vec![(("en".to_owned(), "US".to_owned()), "en-US")]
+            vec![Entry::new("en-US".parse::<LanguageTag>().unwrap(), "en-US")]
         );
 
         assert_eq!(
-            Negotiator::<LanguageNegotiation, _>::new(["en"]).unwrap_err(),
-            Error::MissingSeparator('-')
+            *Negotiator::<LanguageNegotiation, _>::new(["en"])
+                .unwrap()
+                .supported,
+            vec![Entry::new("en".parse::<LanguageTag>().unwrap(), "en")]
         );
 
         assert_eq!(
@@ -188,4 +390,498 @@ mod tests {
             Some(&"en-US")
         );
     }
+
+    #[test]
+    fn negotiate_language_only() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::new(["en", "fr-FR"]).unwrap();
+        for header in ["en", "en-GB", "en-US;q=0.9, fr;q=0.5"] {
+            assert_eq!(negotiator.negotiate(header).unwrap(), Some(&"en"));
+        }
+        assert_eq!(negotiator.negotiate("fr").unwrap(), Some(&"fr-FR"));
+        assert!(negotiator.negotiate("de-DE").unwrap().is_none());
+
+        let negotiator = |tie_break| {
+            Negotiator::<LanguageNegotiation, _>::builder()
+                .extend(["en", "en-GB"])
+                .tie_break(tie_break)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(
+            negotiator(TieBreak::ClientOrder)
+                .negotiate("en-GB")
+                .unwrap(),
+            Some(&"en")
+        );
+        assert_eq!(
+            negotiator(TieBreak::MostSpecific)
+                .negotiate("en-GB")
+                .unwrap(),
+            Some(&"en-GB")
+        );
+        assert_eq!(
+            negotiator(TieBreak::MostSpecific)
+                .negotiate("en-US")
+                .unwrap(),
+            Some(&"en")
+        );
+    }
+
+    #[test]
+    fn negotiate_extended() {
+        let negotiator =
+            Negotiator::<LanguageNegotiation, _>::new(["de-DE", "de-Latn-DE", "fr-CH", "de-x-DE"])
+                .unwrap();
+        assert_eq!(
+            negotiator.negotiate_all("de-*-DE").unwrap(),
+            vec![&"de-DE", &"de-Latn-DE"]
+        );
+        assert_eq!(negotiator.negotiate_all("*-CH").unwrap(), vec![&"fr-CH"]);
+        assert_eq!(
+            negotiator.negotiate("de-Latn-DE, *;q=0.5").unwrap(),
+            Some(&"de-Latn-DE")
+        );
+        assert!(negotiator.negotiate("*-AT").unwrap().is_none());
+        assert_eq!(
+            negotiator.negotiate_all("de-Latn-DE;q=0, de-*-DE").unwrap(),
+            vec![&"de-DE"]
+        );
+
+        assert!(extended_filter(
+            "de-*-DE".split('-'),
+            "de-Latn-DE-1996".split('-')
+        ));
+        assert!(extended_filter("de-DE".split('-'), "de-Deva-DE".split('-')));
+        assert!(!extended_filter("de-DE".split('-'), "de-x-DE".split('-')));
+        assert!(!extended_filter("de-Latn".split('-'), "de-DE".split('-')));
+    }
+
+    #[test]
+    fn negotiate_lookup() {
+        let negotiator =
+            Negotiator::<LanguageNegotiation, _>::new(["en", "zh-Hant", "de-DE", "fr-FR"]).unwrap();
+        let lookup = |header| negotiator.negotiate_lookup(header).unwrap();
+        assert_eq!(lookup("zh-Hant-CN-x-private1"), Some(&"zh-Hant"));
+        assert_eq!(lookup("en-GB, de-DE"), Some(&"en"));
+        assert_eq!(lookup("de-DE-1996;q=0.9, en-US;q=0.5"), Some(&"de-DE"));
+        assert_eq!(lookup("fr, *"), None);
+        assert_eq!(lookup("fr-*-FR, de-*"), None);
+        assert_eq!(lookup("en-US, en;q=0, de-DE;q=0.5"), Some(&"de-DE"));
+        assert_eq!(lookup("it"), None);
+
+        let later = SystemTime::now() + Duration::from_secs(3600);
+        let negotiator = negotiator
+            .with_availability("fr-FR", Availability::from(later))
+            .with_default("en");
+        let lookup = |header| negotiator.negotiate_lookup(header).unwrap();
+        assert_eq!(lookup("fr-FR, zh-Hant;q=0.5"), Some(&"zh-Hant"));
+        assert_eq!(lookup("fr-FR"), Some(&"en"));
+        assert_eq!(lookup("*"), Some(&"en"));
+        assert_eq!(lookup("de"), Some(&"en"));
+        assert_eq!(lookup("de, en;q=0"), None);
+
+        assert_eq!(truncate("Hant-CN-x-private1"), Some("Hant-CN"));
+        assert_eq!(truncate("x-private1"), None);
+        assert_eq!(truncate("CN"), None);
+    }
+
+    #[test]
+    fn negotiate_subtags() {
+        let negotiator =
+            Negotiator::<LanguageNegotiation, _>::new(["zh-Hant-TW", "zh-Hans-CN", "sr-Latn-RS"])
+                .unwrap();
+        for (header, expected) in [
+            ("zh-Hant", Some(&"zh-Hant-TW")),
+            ("zh-TW", Some(&"zh-Hant-TW")),
+            ("zh-Hans", Some(&"zh-Hans-CN")),
+            ("zh-CN", Some(&"zh-Hans-CN")),
+            ("zh-Hant-TW", Some(&"zh-Hant-TW")),
+            ("sr-Latn", Some(&"sr-Latn-RS")),
+            ("sr-RS", Some(&"sr-Latn-RS")),
+            ("zh", Some(&"zh-Hant-TW")),
+            ("zh-Hant-CN", None),
+            ("zh-Hans-TW", None),
+            ("sr-Cyrl", None),
+        ] {
+            assert_eq!(negotiator.negotiate(header).unwrap(), expected, "{header}");
+        }
+        assert_eq!(
+            negotiator.negotiate("zh-Hant;q=0.5, zh-CN").unwrap(),
+            Some(&"zh-Hans-CN")
+        );
+        assert_eq!(
+            negotiator.negotiate_lookup("zh-Hant-TW-x-private").unwrap(),
+            Some(&"zh-Hant-TW")
+        );
+        assert_eq!(negotiator.negotiate_lookup("zh-Hant").unwrap(), None);
+    }
+
+    #[test]
+    fn negotiate_server_order() {
+        let negotiator = |supported: [&'static str; 2], tie_break| {
+            Negotiator::<LanguageNegotiation, _>::builder()
+                .extend(supported)
+                .tie_break(tie_break)
+                .build()
+                .unwrap()
+        };
+        let header = "en-US,en;q=0.9,fr;q=0.9";
+        assert_eq!(
+            negotiator(["fr", "en-GB"], TieBreak::ClientOrder)
+                .negotiate(header)
+                .unwrap(),
+            Some(&"en-GB")
+        );
+        assert_eq!(
+            negotiator(["fr", "en-GB"], TieBreak::ServerOrder)
+                .negotiate(header)
+                .unwrap(),
+            Some(&"fr")
+        );
+        assert_eq!(
+            negotiator(["fr", "en-GB"], TieBreak::ServerOrder)
+                .negotiate("en-GB, fr;q=0.9")
+                .unwrap(),
+            Some(&"en-GB")
+        );
+
+        let header = "de, en-GB;q=0.9, fr;q=0.9";
+        assert_eq!(
+            negotiator(["fr", "en"], TieBreak::ClientOrder)
+                .negotiate_lookup(header)
+                .unwrap(),
+            Some(&"en")
+        );
+        assert_eq!(
+            negotiator(["fr", "en"], TieBreak::ServerOrder)
+                .negotiate_lookup(header)
+                .unwrap(),
+            Some(&"fr")
+        );
+    }
+
+    #[test]
+    fn negotiate_private_use() {
+        let negotiator =
+            Negotiator::<LanguageNegotiation, _>::new(["en-US", "x-pirate", "en-x-custom"])
+                .unwrap();
+        assert_eq!(negotiator.negotiate("x-pirate").unwrap(), Some(&"x-pirate"));
+        assert_eq!(negotiator.negotiate("X-Pirate").unwrap(), Some(&"x-pirate"));
+        assert_eq!(
+            negotiator.negotiate("en-x-custom").unwrap(),
+            Some(&"en-x-custom")
+        );
+        assert_eq!(
+            negotiator.negotiate_all("en").unwrap(),
+            vec![&"en-US", &"en-x-custom"]
+        );
+        assert!(negotiator.negotiate("en-custom").unwrap().is_none());
+        assert!(negotiator.negotiate("x-ninja").unwrap().is_none());
+        assert_eq!(
+            negotiator.negotiate_lookup("x-pirate-ship").unwrap(),
+            Some(&"x-pirate")
+        );
+        assert_eq!(
+            negotiator.negotiate_lookup("en-x-custom").unwrap(),
+            Some(&"en-x-custom")
+        );
+    }
+
+    #[test]
+    fn negotiate_legacy_codes() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::new(["en", "iw", "id-ID"]).unwrap();
+        assert_eq!(negotiator.negotiate("he-IL").unwrap(), Some(&"iw"));
+        assert_eq!(negotiator.negotiate("iw").unwrap(), Some(&"iw"));
+        assert_eq!(
+            negotiator.negotiate("in, en;q=0.5").unwrap(),
+            Some(&"id-ID")
+        );
+        assert_eq!(
+            negotiator.negotiate_lookup("IN-ID").unwrap(),
+            Some(&"id-ID")
+        );
+        assert!(negotiator.negotiate("ji").unwrap().is_none());
+        assert_eq!(
+            negotiator
+                .negotiate_detailed("iw-IL")
+                .unwrap()
+                .map(|n| n.range),
+            Some("he-IL".to_owned())
+        );
+    }
+
+    #[test]
+    fn negotiate_grandfathered() {
+        let negotiator =
+            Negotiator::<LanguageNegotiation, _>::new(["i-klingon", "nb-NO", "i-default"]).unwrap();
+        assert_eq!(negotiator.negotiate("tlh").unwrap(), Some(&"i-klingon"));
+        assert_eq!(
+            negotiator.negotiate("i-klingon").unwrap(),
+            Some(&"i-klingon")
+        );
+        assert_eq!(negotiator.negotiate("no-bok").unwrap(), Some(&"nb-NO"));
+        assert_eq!(
+            negotiator.negotiate("i-default").unwrap(),
+            Some(&"i-default")
+        );
+        assert_eq!(
+            negotiator.negotiate_lookup("i-default").unwrap(),
+            Some(&"i-default")
+        );
+        assert!(negotiator.negotiate("i-enochian").unwrap().is_none());
+    }
+
+    #[test]
+    fn negotiate_case_insensitive() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::new(["en-US", "zh-Hant"]).unwrap();
+        assert_eq!(negotiator.negotiate("en-us").unwrap(), Some(&"en-US"));
+        assert_eq!(negotiator.negotiate("EN").unwrap(), Some(&"en-US"));
+        assert_eq!(negotiator.negotiate("zh-hant").unwrap(), Some(&"zh-Hant"));
+        assert_eq!(
+            negotiator.negotiate_lookup("zh-HANT-tw").unwrap(),
+            Some(&"zh-Hant")
+        );
+        assert_eq!(
+            negotiator.negotiate_lookup("en-us;q=0, EN-US").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn negotiate_wildcard() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::new(["en-US", "fr-FR"]).unwrap();
+        assert_eq!(negotiator.negotiate("*").unwrap(), Some(&"en-US"));
+        assert_eq!(
+            negotiator.negotiate("fr-FR, *;q=0.1").unwrap(),
+            Some(&"fr-FR")
+        );
+        assert_eq!(
+            negotiator.negotiate_with_quality("*;q=0.1, de").unwrap(),
+            Some((&"en-US", Quality::from_thousandths(100).unwrap()))
+        );
+        assert_eq!(
+            negotiator.negotiate("*;q=0.1, en-US;q=0").unwrap(),
+            Some(&"fr-FR")
+        );
+        assert_eq!(
+            negotiator.negotiate_all("*;q=0, fr").unwrap(),
+            vec![&"fr-FR"]
+        );
+        assert!(negotiator.negotiate("de, *;q=0").unwrap().is_none());
+        assert_eq!(
+            negotiator
+                .negotiate_detailed(" * ;q=0.5")
+                .unwrap()
+                .map(|n| n.range),
+            Some("*".to_owned())
+        );
+        assert_eq!(
+            LanguageNegotiation::parse_header_elem("*;q=0.1"),
+            Ok((
+                ("*", MaybeWildcard::Wildcard),
+                Quality::from_thousandths(100).unwrap()
+            ))
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn content_language() {
+        use super::content_language;
+
+        assert_eq!(content_language(["en-US"]).unwrap(), "en-US");
+        assert_eq!(
+            content_language(vec!["mi".to_owned(), " en".to_owned(), "MI".to_owned()]).unwrap(),
+            "mi, en"
+        );
+        assert_eq!(
+            content_language(["en", "fr;q=1"]).unwrap_err(),
+            Error::ParamsNotAllowed
+        );
+        assert_eq!(
+            content_language(["en", "*"]).unwrap_err(),
+            Error::InvalidLanguageTag
+        );
+        assert_eq!(
+            content_language(Vec::<&str>::new()).unwrap_err(),
+            Error::InvalidHeader
+        );
+    }
+
+    #[test]
+    fn negotiate_empty() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::new(["en-US", "fr-FR"]).unwrap();
+        assert_eq!(negotiator.negotiate("").unwrap(), Some(&"en-US"));
+        assert_eq!(negotiator.negotiate(" ").unwrap(), Some(&"en-US"));
+    }
+
+    #[test]
+    fn negotiate_all() {
+        assert_eq!(
+            Negotiator::<LanguageNegotiation, _>::new(["en-US", "fr-FR", "de-DE"])
+                .unwrap()
+                .negotiate_all("fr, en-US;q=0.5")
+                .unwrap(),
+            vec![&"fr-FR", &"en-US"]
+        );
+    }
+
+    #[test]
+    fn negotiate_all_with_quality() {
+        assert_eq!(
+            Negotiator::<LanguageNegotiation, _>::new(["en-US", "fr-FR", "de-DE"])
+                .unwrap()
+                .negotiate_all_with_quality("fr, en;q=0.5, de-DE;q=0")
+                .unwrap(),
+            vec![
+                (&"fr-FR", Quality::ONE),
+                (&"en-US", Quality::from_thousandths(500).unwrap())
+            ]
+        );
+    }
+
+    #[test]
+    fn outcome_demand() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::{parse_accept_language, MaybeWildcard};
+
+        // Records the languages asked for with q >= 0.8 when the client only got the fallback.
+        let missed = Arc::new(Mutex::new(Vec::new()));
+        let negotiator = Negotiator::<LanguageNegotiation, _>::builder()
+            .extend(["en", "fr"])
+            .build()
+            .unwrap()
+            .on_outcome({
+                let missed = Arc::clone(&missed);
+                move |outcome| {
+                    if outcome
+                        .quality
+                        .is_some_and(|q| q >= Quality::from_thousandths(800).unwrap())
+                    {
+                        return;
+                    }
+                    let Some(Ok(ranges)) = outcome.header.map(parse_accept_language) else {
+                        return;
+                    };
+                    missed.lock().unwrap().extend(ranges.into_iter().filter_map(
+                        |(tag, q)| match tag {
+                            MaybeWildcard::Specific(tag)
+                                if q >= Quality::from_thousandths(800).unwrap() =>
+                            {
+                                Some(tag.to_string())
+                            }
+                            _ => None,
+                        },
+                    ));
+                }
+            });
+        assert_eq!(
+            negotiator.negotiate_with_quality("ja, en;q=0.1").unwrap(),
+            Some((&"en", Quality::from_thousandths(100).unwrap()))
+        );
+        assert_eq!(negotiator.negotiate("fr-CA, ja").unwrap(), Some(&"fr"));
+        assert_eq!(
+            negotiator.negotiate("ja-JP, ko;q=0.9, en;q=0.5").unwrap(),
+            Some(&"en")
+        );
+        assert_eq!(*missed.lock().unwrap(), ["ja", "ja-JP", "ko"]);
+    }
+
+    #[test]
+    fn validate_config() {
+        assert_eq!(
+            Negotiator::<LanguageNegotiation, String>::validate_config([
+                "en-US",
+                "fr",
+                "de-DE;q=1"
+            ]),
+            vec![(2, Error::ParamsNotAllowed)]
+        );
+    }
+
+    #[test]
+    fn negotiate_with_quality() {
+        assert_eq!(
+            Negotiator::<LanguageNegotiation, _>::new(["en-US", "fr-FR"])
+                .unwrap()
+                .negotiate_with_quality("de, fr;q=0.7, en;q=0.3")
+                .unwrap(),
+            Some((&"fr-FR", Quality::from_thousandths(700).unwrap()))
+        );
+    }
+
+    #[test]
+    fn negotiate_strict() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::new(["en-US", "fr-FR"]).unwrap();
+        assert_eq!(negotiator.negotiate_strict("fr").unwrap(), &"fr-FR");
+        assert_eq!(
+            negotiator.negotiate_strict("de-DE").unwrap_err(),
+            Error::NotAcceptable
+        );
+        assert_eq!(
+            negotiator.negotiate_strict("de-DE;q=x").unwrap_err(),
+            Error::InvalidQuality {
+                source: ParseQualityError::Malformed
+            }
+        );
+    }
+
+    #[test]
+    fn negotiate_multi() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::new(["en-US", "fr-FR"]).unwrap();
+        assert_eq!(
+            negotiator
+                .negotiate_multi(["en;q=0.5", "", "fr-FR;q=0.8"])
+                .unwrap(),
+            Some(&"fr-FR")
+        );
+        assert!(negotiator.negotiate_multi(["de", "it"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn negotiate_excluded() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::new(["en-US", "en-GB"]).unwrap();
+        assert_eq!(
+            negotiator.negotiate("en, en-US;q=0").unwrap(),
+            Some(&"en-GB")
+        );
+        assert!(negotiator.negotiate("en-GB;q=0").unwrap().is_none());
+    }
+
+    #[test]
+    fn borrowed() {
+        let config = "en-US fr-FR".to_owned();
+        let values = config.split(' ').collect::<Vec<_>>();
+        let mut negotiator = Negotiator::<LanguageNegotiation, _>::borrowed(&values).unwrap();
+        assert!(matches!(
+            negotiator.negotiate("fr").unwrap(),
+            Some(Cow::Borrowed("fr-FR"))
+        ));
+
+        negotiator.push(Cow::Owned("de-DE".to_owned())).unwrap();
+        let negotiator: Negotiator<LanguageNegotiation, Cow<'static, str>> =
+            negotiator.map(|value| Cow::Owned(value.into_owned()));
+        drop(config);
+        assert_eq!(
+            negotiator
+                .negotiate("de, en;q=0.5")
+                .unwrap()
+                .map(Cow::as_ref),
+            Some("de-DE")
+        );
+    }
+
+    #[test]
+    fn supported() {
+        let negotiator = Negotiator::<LanguageNegotiation, _>::new(["en-US", "fr-FR"]).unwrap();
+        assert_eq!(
+            negotiator.supported().collect::<Vec<_>>(),
+            vec![&"en-US", &"fr-FR"]
+        );
+        assert_eq!(
+            negotiator.supported_parsed().next(),
+            Some((&"en-US".parse::<LanguageTag>().unwrap(), &"en-US"))
+        );
+    }
 }
@@ -0,0 +1,421 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    iter,
+    str::FromStr,
+};
+
+use crate::{Error, HeaderElements, LanguageNegotiation, MaybeWildcard, NegotiationType, Quality};
+
+/// A supported BCP 47 language tag (RFC 5646), split into its parts with their case preserved,
+/// e.g. `zh-Hant-TW` or `de-DE-1996`. Tags are compared ignoring case, so `zh-hant` equals
+/// `zh-Hant`.
+#[derive(Clone, Debug)]
+pub struct LanguageTag {
+    // Empty for a private use tag.
+    language: String,
+    extlangs: Vec<String>,
+    script: Option<String>,
+    region: Option<String>,
+    variants: Vec<String>,
+    // Each extension with its singleton, e.g. `u-co-phonebk`.
+    extensions: Vec<String>,
+    private_use: Vec<String>,
+}
+
+// RFC 5646 section 2.2.8, with the preferred value of each tag when the registry defines one.
+pub(crate) const GRANDFATHERED: &[(&str, Option<&str>)] = &[
+    ("en-GB-oed", Some("en-GB-oxendict")),
+    ("i-ami", Some("ami")),
+    ("i-bnn", Some("bnn")),
+    ("i-default", None),
+    ("i-enochian", None),
+    ("i-hak", Some("hak")),
+    ("i-klingon", Some("tlh")),
+    ("i-lux", Some("lb")),
+    ("i-mingo", None),
+    ("i-navajo", Some("nv")),
+    ("i-pwn", Some("pwn")),
+    ("i-tao", Some("tao")),
+    ("i-tay", Some("tay")),
+    ("i-tsu", Some("tsu")),
+    ("sgn-BE-FR", Some("sfb")),
+    ("sgn-BE-NL", Some("vgt")),
+    ("sgn-CH-DE", Some("sgg")),
+    ("art-lojban", Some("jbo")),
+    ("cel-gaulish", None),
+    ("no-bok", Some("nb")),
+    ("no-nyn", Some("nn")),
+    ("zh-guoyu", Some("cmn")),
+    ("zh-hakka", Some("hak")),
+    ("zh-min", None),
+    ("zh-min-nan", Some("nan")),
+    ("zh-xiang", Some("hsn")),
+];
+
+/// The preferred value of a grandfathered tag, or the tag itself.
+pub(crate) fn preferred(tag: &str) -> &str {
+    match GRANDFATHERED
+        .iter()
+        .find(|(grandfathered, _)| grandfathered.eq_ignore_ascii_case(tag))
+    {
+        Some((_, Some(preferred))) => preferred,
+        _ => tag,
+    }
+}
+
+// Language codes withdrawn from ISO 639 along with their replacement.
+const LEGACY_LANGUAGES: &[(&str, &str)] = &[
+    ("in", "id"),
+    ("iw", "he"),
+    ("ji", "yi"),
+    ("jw", "jv"),
+    ("mo", "ro"),
+];
+
+/// The modern code of a withdrawn primary language, e.g. `he` for `iw`, or the language itself.
+pub(crate) fn modern_language(language: &str) -> &str {
+    LEGACY_LANGUAGES
+        .iter()
+        .find(|(legacy, _)| legacy.eq_ignore_ascii_case(language))
+        .map_or(language, |(_, modern)| modern)
+}
+
+impl LanguageTag {
+    pub(crate) fn parse(input: &str) -> Result<Self, Error> {
+        let input = preferred(input);
+        // Grandfathered tags left without a preferred value don't follow the grammar, they are
+        // kept as they are.
+        if GRANDFATHERED
+            .iter()
+            .any(|(tag, _)| tag.eq_ignore_ascii_case(input))
+        {
+            let mut subtags = input.split('-').map(str::to_owned);
+            return Ok(Self {
+                language: subtags.next().unwrap_or_default(),
+                extlangs: Vec::new(),
+                script: None,
+                region: None,
+                variants: subtags.collect(),
+                extensions: Vec::new(),
+                private_use: Vec::new(),
+            });
+        }
+
+        let mut subtags = input.split('-').peekable();
+        if subtags
+            .clone()
+            .any(|s| s.is_empty() || s.len() > 8 || !s.bytes().all(|b| b.is_ascii_alphanumeric()))
+        {
+            return Err(Error::InvalidLanguageTag);
+        }
+
+        // A private use tag, e.g. `x-pirate`, has no language and is only made of private use
+        // subtags.
+        let language = match subtags.peek().is_some_and(|s| s.eq_ignore_ascii_case("x")) {
+            true => String::new(),
+            false => subtags
+                .next()
+                .filter(|s| s.len() >= 2 && is_alpha(s))
+                .map(modern_language)
+                .ok_or(Error::InvalidLanguageTag)?
+                .to_owned(),
+        };
+        let mut tag = Self {
+            extlangs: Vec::new(),
+            script: None,
+            region: None,
+            variants: Vec::new(),
+            extensions: Vec::new(),
+            private_use: Vec::new(),
+            language,
+        };
+        if tag.language.len() <= 3 {
+            while tag.extlangs.len() < 3 {
+                match subtags.next_if(|s| s.len() == 3 && is_alpha(s)) {
+                    Some(extlang) => tag.extlangs.push(extlang.to_owned()),
+                    None => break,
+                }
+            }
+        }
+        tag.script = subtags
+            .next_if(|s| s.len() == 4 && is_alpha(s))
+            .map(str::to_owned);
+        tag.region = subtags
+            .next_if(|s| (s.len() == 2 && is_alpha(s)) || (s.len() == 3 && is_digit(s)))
+            .map(str::to_owned);
+        while let Some(variant) =
+            subtags.next_if(|s| s.len() >= 5 || (s.len() == 4 && s.as_bytes()[0].is_ascii_digit()))
+        {
+            tag.variants.push(variant.to_owned());
+        }
+        while let Some(singleton) =
+            subtags.next_if(|s| s.len() == 1 && !s.eq_ignore_ascii_case("x"))
+        {
+            let mut extension = singleton.to_owned();
+            while let Some(s) = subtags.next_if(|s| s.len() >= 2) {
+                extension.push('-');
+                extension.push_str(s);
+            }
+            if extension.len() == 1 {
+                return Err(Error::InvalidLanguageTag);
+            }
+            tag.extensions.push(extension);
+        }
+        if subtags.next_if(|s| s.eq_ignore_ascii_case("x")).is_some() {
+            tag.private_use = subtags.by_ref().map(str::to_owned).collect();
+            if tag.private_use.is_empty() {
+                return Err(Error::InvalidLanguageTag);
+            }
+        }
+        if subtags.next().is_some() {
+            return Err(Error::InvalidLanguageTag);
+        }
+        Ok(tag)
+    }
+
+    /// Every subtag in order, the private use ones following an `x`.
+    pub(crate) fn subtags(&self) -> impl Iterator<Item = &str> + Clone {
+        iter::once(self.language.as_str())
+            .filter(|language| !language.is_empty())
+            .chain(self.extlangs.iter().map(String::as_str))
+            .chain(self.script.as_deref())
+            .chain(self.region.as_deref())
+            .chain(self.variants.iter().map(String::as_str))
+            .chain(self.extensions.iter().flat_map(|e| e.split('-')))
+            .chain(
+                (!self.private_use.is_empty())
+                    .then_some("x")
+                    .into_iter()
+                    .chain(self.private_use.iter().map(String::as_str)),
+            )
+    }
+
+    /// The primary language subtag, e.g. `zh` for `zh-Hant-TW`, empty for a private use tag.
+    pub fn primary(&self) -> &str {
+        &self.language
+    }
+
+    /// The extended language subtags, e.g. `yue` for `zh-yue-HK`.
+    pub fn extlangs(&self) -> impl Iterator<Item = &str> {
+        self.extlangs.iter().map(String::as_str)
+    }
+
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub fn variants(&self) -> impl Iterator<Item = &str> {
+        self.variants.iter().map(String::as_str)
+    }
+
+    /// The private use subtags, following the `x` singleton.
+    pub fn private_use(&self) -> impl Iterator<Item = &str> {
+        self.private_use.iter().map(String::as_str)
+    }
+
+    /// Whether the tag is only made of a primary language, e.g. `en`.
+    pub(crate) fn is_language_only(&self) -> bool {
+        self.subtags().nth(1).is_none()
+    }
+}
+
+impl PartialEq for LanguageTag {
+    fn eq(&self, other: &Self) -> bool {
+        subtags_eq(self.subtags(), other.subtags())
+    }
+}
+
+impl Eq for LanguageTag {}
+
+impl Hash for LanguageTag {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for subtag in self.subtags() {
+            for b in subtag.bytes() {
+                state.write_u8(b.to_ascii_lowercase());
+            }
+            state.write_u8(b'-');
+        }
+    }
+}
+
+impl fmt::Display for LanguageTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, subtag) in self.subtags().enumerate() {
+            if i > 0 {
+                f.write_str("-")?;
+            }
+            f.write_str(subtag)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for LanguageTag {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LanguageNegotiation::parse_elem(&s)
+    }
+}
+
+pub(crate) fn subtags_eq<'a, 'b>(
+    mut a: impl Iterator<Item = &'a str>,
+    mut b: impl Iterator<Item = &'b str>,
+) -> bool {
+    loop {
+        match (a.next(), b.next()) {
+            (Some(a), Some(b)) if a.eq_ignore_ascii_case(b) => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// Parses every language range of an `Accept-Language` header as a [`LanguageTag`] with its
+/// quality, in the order sent by the client, without negotiating. A bare `*` is a wildcard while
+/// ranges with a `*` subtag, e.g. `de-*-DE`, fail with [`Error::InvalidLanguageTag`].
+pub fn parse_accept_language(
+    header: &str,
+) -> Result<Vec<(MaybeWildcard<LanguageTag>, Quality)>, Error> {
+    HeaderElements::<LanguageNegotiation>::new(header)
+        .map(|elem| {
+            let (range, quality) = elem?;
+            let tag = match LanguageNegotiation::is_wildcard(&range) {
+                true => MaybeWildcard::Wildcard,
+                false => MaybeWildcard::Specific(LanguageTag::parse(
+                    &LanguageNegotiation::format_range(&range),
+                )?),
+            };
+            Ok((tag, quality))
+        })
+        .collect()
+}
+
+fn is_alpha(subtag: &str) -> bool {
+    subtag.bytes().all(|b| b.is_ascii_alphabetic())
+}
+
+fn is_digit(subtag: &str) -> bool {
+    subtag.bytes().all(|b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_accept_language, LanguageTag};
+    use crate::{Error, MaybeWildcard, Quality};
+
+    fn parts(tag: &str) -> LanguageTag {
+        tag.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let tag = parts("zh-Hant-TW");
+        assert_eq!(tag.language, "zh");
+        assert_eq!(tag.script.as_deref(), Some("Hant"));
+        assert_eq!(tag.region.as_deref(), Some("TW"));
+
+        let tag = parts("sr-Latn");
+        assert_eq!(tag.script.as_deref(), Some("Latn"));
+        assert_eq!(tag.region, None);
+
+        let tag = parts("de-DE-1996");
+        assert_eq!(tag.region.as_deref(), Some("DE"));
+        assert_eq!(tag.variants, ["1996"]);
+
+        let tag = parts("zh-yue-HK");
+        assert_eq!(tag.extlangs, ["yue"]);
+        assert_eq!(tag.region.as_deref(), Some("HK"));
+
+        let tag = parts("es-419");
+        assert_eq!(tag.region.as_deref(), Some("419"));
+
+        let tag = parts("de-CH-1901-u-co-phonebk-x-private");
+        assert_eq!(tag.variants, ["1901"]);
+        assert_eq!(tag.extensions, ["u-co-phonebk"]);
+        assert_eq!(tag.private_use, ["private"]);
+        assert_eq!(
+            tag.subtags().collect::<Vec<_>>(),
+            ["de", "CH", "1901", "u", "co", "phonebk", "x", "private"]
+        );
+
+        assert_eq!(parts("zh-hant-tw"), parts("zh-Hant-TW"));
+        assert_ne!(parts("zh-Hant"), parts("zh-Hant-TW"));
+        assert_eq!(parts("zh-hant").script.as_deref(), Some("hant"));
+
+        assert_eq!(parts("i-klingon"), parts("tlh"));
+        assert_eq!(parts("EN-gb-OED"), parts("en-GB-oxendict"));
+        assert_eq!(parts("zh-min-nan"), parts("nan"));
+        let tag = parts("i-default");
+        assert_eq!(tag.language, "i");
+        assert_eq!(tag.subtags().collect::<Vec<_>>(), ["i", "default"]);
+        assert_eq!(parts("zh-min").subtags().collect::<Vec<_>>(), ["zh", "min"]);
+
+        let tag = parts("x-pirate");
+        assert_eq!(tag.language, "");
+        assert_eq!(tag.private_use, ["pirate"]);
+        assert_eq!(tag.subtags().collect::<Vec<_>>(), ["x", "pirate"]);
+        assert!(!tag.is_language_only());
+        assert_eq!(parts("en-x-custom").private_use, ["custom"]);
+
+        let tag = parts("zh-yue-Hant-HK-1996");
+        assert_eq!(tag.primary(), "zh");
+        assert_eq!(tag.extlangs().collect::<Vec<_>>(), ["yue"]);
+        assert_eq!(tag.script(), Some("Hant"));
+        assert_eq!(tag.region(), Some("HK"));
+        assert_eq!(tag.variants().collect::<Vec<_>>(), ["1996"]);
+        assert_eq!(tag.to_string(), "zh-yue-Hant-HK-1996");
+        assert_eq!(parts("x-Pirate").to_string(), "x-Pirate");
+        assert_eq!(parts("i-default").to_string(), "i-default");
+        assert_eq!(parts("i-klingon").to_string(), "tlh");
+
+        assert_eq!(parts("iw-IL").to_string(), "he-IL");
+        assert_eq!(parts("IN"), parts("id"));
+        assert_eq!(parts("ji").primary(), "yi");
+        assert_eq!(parts("ind").primary(), "ind");
+
+        assert!(parts("en").is_language_only());
+        assert!(!parts("en-US").is_language_only());
+
+        for invalid in [
+            "",
+            "e",
+            "en-",
+            "en--US",
+            "en-US-u",
+            "en-x",
+            "en-US-verylongsubtag",
+            "1a",
+            "i-unknown",
+            "x",
+            "x-",
+        ] {
+            assert_eq!(
+                invalid.parse::<LanguageTag>(),
+                Err(Error::InvalidLanguageTag),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn accept_language() {
+        let ranges = parse_accept_language("fr-CH, fr;q=0.9, *;q=0.5").unwrap();
+        assert_eq!(ranges.len(), 3);
+        let MaybeWildcard::Specific(tag) = &ranges[0].0 else {
+            panic!("fr-CH is not a wildcard");
+        };
+        assert_eq!((tag.primary(), tag.region()), ("fr", Some("CH")));
+        assert_eq!(ranges[1].1, Quality::from_thousandths(900).unwrap());
+        assert_eq!(ranges[2].0, MaybeWildcard::Wildcard);
+        assert_eq!(
+            parse_accept_language("de-*-DE").unwrap_err(),
+            Error::InvalidLanguageTag
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use ::language_tags::LanguageTag;
+
+use crate::{
+    parse_accept_language, AsNegotiationStr, Error, LanguageNegotiation, MaybeWildcard, Negotiator,
+    Quality,
+};
+
+impl Negotiator<LanguageNegotiation, LanguageTag> {
+    /// Builds a negotiator supporting [`LanguageTag`] values, returned as is when selected.
+    pub fn from_language_tags<I>(iter: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = LanguageTag>,
+    {
+        Ok(Negotiator::<LanguageNegotiation, String>::new(
+            iter.into_iter().map(LanguageTag::into_string),
+        )?
+        .map(|value| LanguageTag::parse(&value).expect("supported values are language tags")))
+    }
+}
+
+impl<T: AsNegotiationStr> Negotiator<LanguageNegotiation, T> {
+    /// Negotiates like [`Negotiator::negotiate`], returning the selected value as a
+    /// [`LanguageTag`].
+    ///
+    /// Meant for negotiators over strings, as those built with
+    /// [`Negotiator::from_language_tags`] already select `LanguageTag` values.
+    pub fn negotiate_language_tag(&self, header: &str) -> Result<Option<LanguageTag>, Error> {
+        self.negotiate(header)?
+            .map(|value| {
+                value
+                    .as_str()
+                    .parse()
+                    .map_err(|_| Error::InvalidLanguageTag)
+            })
+            .transpose()
+    }
+}
+
+/// Parses an `Accept-Language` header with [`parse_accept_language`], converting each range to
+/// a [`LanguageTag`] of the `language-tags` crate.
+pub fn parse_accept_language_tags(
+    header: &str,
+) -> Result<Vec<(MaybeWildcard<LanguageTag>, Quality)>, Error> {
+    parse_accept_language(header)?
+        .into_iter()
+        .map(|(tag, quality)| {
+            let tag = match tag {
+                MaybeWildcard::Wildcard => MaybeWildcard::Wildcard,
+                MaybeWildcard::Specific(tag) => MaybeWildcard::Specific(
+                    LanguageTag::parse(&tag.to_string()).map_err(|_| Error::InvalidLanguageTag)?,
+                ),
+            };
+            Ok((tag, quality))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ::language_tags::LanguageTag;
+
+    use super::parse_accept_language_tags;
+    use crate::{Error, LanguageNegotiation, MaybeWildcard, Negotiator, Quality};
+
+    #[test]
+    fn negotiate() {
+        let negotiator = Negotiator::from_language_tags([
+            LanguageTag::parse("en-US").unwrap(),
+            LanguageTag::parse("zh-Hant-TW").unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(
+            negotiator
+                .negotiate("zh-Hant")
+                .unwrap()
+                .map(LanguageTag::as_str),
+            Some("zh-Hant-TW")
+        );
+
+        let negotiator = Negotiator::<LanguageNegotiation, _>::new(["en-US", "fr-FR"]).unwrap();
+        assert_eq!(
+            negotiator.negotiate_language_tag("fr").unwrap(),
+            Some(LanguageTag::parse("fr-FR").unwrap())
+        );
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            parse_accept_language_tags("fr-CH, fr;q=0.9, *;q=0.5").unwrap(),
+            vec![
+                (
+                    MaybeWildcard::Specific(LanguageTag::parse("fr-CH").unwrap()),
+                    Quality::ONE
+                ),
+                (
+                    MaybeWildcard::Specific(LanguageTag::parse("fr").unwrap()),
+                    Quality::from_thousandths(900).unwrap()
+                ),
+                (
+                    MaybeWildcard::Wildcard,
+                    Quality::from_thousandths(500).unwrap()
+                ),
+            ]
+        );
+        assert_eq!(
+            parse_accept_language_tags("de-*-DE").unwrap_err(),
+            Error::InvalidLanguageTag
+        );
+    }
+}
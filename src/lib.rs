@@ -1,27 +1,105 @@
+mod availability;
 #[cfg(feature = "axum")]
 mod axum;
+mod builder;
+#[cfg(feature = "compat")]
+pub mod compat;
+#[cfg(feature = "serde")]
+mod config;
 mod content_type;
+mod elements;
 mod encoding;
 mod error;
+mod explain;
+#[cfg(feature = "fluent")]
+mod fluent;
+mod header_builder;
+#[cfg(feature = "icu")]
+mod icu;
+mod index;
 mod language;
+mod language_tag;
+#[cfg(feature = "language-tags")]
+mod language_tags;
+mod lint;
+mod macros;
+mod media_type;
+pub mod media_types;
+#[cfg(feature = "mime")]
+mod mime;
+#[cfg(feature = "otel")]
+mod otel;
+mod outcome;
+mod params;
+#[cfg(feature = "propagation")]
+mod propagation;
+#[cfg(feature = "proxy-wasm")]
+pub mod proxy_wasm;
+mod quality;
+mod registry;
+mod simulation;
+mod token;
 
-use std::{borrow::Borrow, collections::BTreeMap, ops::Deref};
+use std::{borrow::Cow, cmp::Ordering, fmt, slice, sync::Arc, time::SystemTime, vec};
 
+use builder::Options;
+use index::Supported;
+use params::split_unquoted;
+use token::AnyTokenNegotiation;
+
+pub use availability::*;
+pub use builder::{
+    DuplicatePolicy, Limits, MatchOptions, NegotiatorBuilder, ParamMatching, Scoring, TieBreak,
+    UnknownParams, Weighting, WildcardPolicy,
+};
+#[cfg(feature = "serde")]
+pub use config::*;
 pub use content_type::*;
+pub use elements::*;
 pub use encoding::*;
 pub use error::Error;
+pub use explain::*;
+#[cfg(feature = "fluent")]
+pub use fluent::BundleNegotiator;
+pub use header_builder::HeaderBuilder;
 pub use language::*;
+pub use language_tag::{parse_accept_language, LanguageTag};
+#[cfg(feature = "language-tags")]
+pub use language_tags::parse_accept_language_tags;
+pub use lint::*;
+pub use macros::*;
+pub use media_type::{parse_accept, MediaRange, MediaType};
+#[cfg(feature = "mime")]
+pub use mime::parse_accept_mime;
+#[cfg(feature = "otel")]
+pub use otel::*;
+pub use outcome::NegotiationOutcome;
+pub use params::HeaderParams;
+#[cfg(feature = "propagation")]
+pub use propagation::*;
+pub use quality::{ParseQualityError, Quality};
+pub use registry::*;
+pub use simulation::*;
+pub use token::*;
 
 #[cfg(feature = "axum")]
 pub use crate::axum::*;
+#[cfg(feature = "macros")]
+pub use http_negotiator_macros::produces;
+#[cfg(feature = "derive")]
+pub use http_negotiator_macros::AsNegotiationStr;
+
+#[cfg(feature = "derive")]
+extern crate self as http_negotiator;
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
 pub enum MaybeWildcard<T> {
     Specific(T),
     Wildcard,
 }
 
 impl<T> MaybeWildcard<T> {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str<'a>(input: T) -> MaybeWildcard<T>
     where
         T: PartialEq<&'a str>,
@@ -44,12 +122,51 @@ impl<T> MaybeWildcard<T> {
     }
 }
 
+impl<T: fmt::Display> fmt::Display for MaybeWildcard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaybeWildcard::Specific(s) => s.fmt(f),
+            MaybeWildcard::Wildcard => f.write_str("*"),
+        }
+    }
+}
+
 impl<'a> From<&'a str> for MaybeWildcard<&'a str> {
     fn from(s: &'a str) -> Self {
         Self::from_str(s)
     }
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum NegotiationKind {
+    ContentType,
+    Language,
+    Encoding,
+    Token,
+}
+
+#[cfg(feature = "http")]
+impl NegotiationKind {
+    pub fn from_header_name(name: &http::header::HeaderName) -> Option<Self> {
+        match *name {
+            http::header::ACCEPT => Some(Self::ContentType),
+            http::header::ACCEPT_LANGUAGE => Some(Self::Language),
+            http::header::ACCEPT_ENCODING => Some(Self::Encoding),
+            _ => None,
+        }
+    }
+
+    /// The header negotiated by this kind, `None` for token lists which can use any header.
+    pub fn header_name(&self) -> Option<http::header::HeaderName> {
+        match self {
+            Self::ContentType => Some(ContentTypeNegotiation::associated_header()),
+            Self::Language => Some(LanguageNegotiation::associated_header()),
+            Self::Encoding => Some(EncodingNegotiation::associated_header()),
+            Self::Token => None,
+        }
+    }
+}
+
 pub trait AsNegotiationStr {
     fn as_str(&self) -> &str;
 }
@@ -61,22 +178,295 @@ impl<T: AsRef<str>> AsNegotiationStr for T {
 }
 
 pub trait NegotiationType {
-    type Parsed;
+    type Parsed: PartialEq;
+    type Range<'a>: Clone + PartialEq;
+
+    const KIND: NegotiationKind;
 
     fn parse_elem<M: AsNegotiationStr>(input: &M) -> Result<Self::Parsed, Error>;
 
-    fn parse_and_negotiate_header<'a, T>(
-        supported: &'a [(Self::Parsed, T)],
-        header: &str,
-    ) -> Result<Option<&'a T>, Error>;
+    /// Parses a supported value which may contain wildcards, only used when the negotiator allows
+    /// them.
+    fn parse_wildcard_elem<M: AsNegotiationStr>(input: &M) -> Result<Self::Parsed, Error> {
+        Self::parse_elem(input)
+    }
 
-    #[cfg(feature = "axum")]
+    fn parse_header_elem(input: &str) -> Result<(Self::Range<'_>, Quality), Error>;
+
+    /// Parses a header element accepting the quirks of old and embedded browsers, see
+    /// [`NegotiatorBuilder::browser_quirks`].
+    fn parse_quirky_header_elem(input: &str) -> Result<(Self::Range<'_>, Quality), Error> {
+        Self::parse_header_elem(input)
+    }
+
+    /// The range an empty header stands for, e.g. `*/*` for `Accept`. By default an empty header
+    /// accepts nothing.
+    fn empty_header<'a>() -> Option<(Self::Range<'a>, Quality)> {
+        None
+    }
+
+    /// A range the header accepts without listing it, given the ranges it lists, e.g. `identity`
+    /// for `Accept-Encoding`. It is ranked after the listed ranges of the same quality.
+    fn implicit_range<'a>(
+        ranges: &[(Self::Range<'a>, Quality)],
+    ) -> Option<(Self::Range<'a>, Quality)> {
+        let _ = ranges;
+        None
+    }
+
+    /// Orders ranges of equal quality, the greatest first. The specificity of the range comes
+    /// first and must always outweigh the parameter count that follows it.
+    fn precedence(range: &Self::Range<'_>) -> (u8, usize);
+
+    fn specificity(_supported: &Self::Parsed) -> usize {
+        0
+    }
+
+    fn matches(supported: &Self::Parsed, range: &Self::Range<'_>) -> bool;
+
+    /// Like [`NegotiationType::matches`], following the negotiator's parameter options. Types
+    /// without parameters can ignore them.
+    fn matches_with(
+        supported: &Self::Parsed,
+        range: &Self::Range<'_>,
+        _options: MatchOptions,
+    ) -> bool {
+        Self::matches(supported, range)
+    }
+
+    /// Whether the range matches the supported value through a structured syntax suffix, only
+    /// used when suffix matching is enabled.
+    fn matches_suffix(
+        _supported: &Self::Parsed,
+        _range: &Self::Range<'_>,
+        _options: MatchOptions,
+    ) -> bool {
+        false
+    }
+
+    /// Whether the supported value declares a parameter named `name`, ignoring case.
+    fn has_param(_supported: &Self::Parsed, _name: &str) -> bool {
+        false
+    }
+
+    /// The range without the parameters whose name is rejected by `keep`, see
+    /// [`UnknownParams`].
+    fn retain_params<'a>(range: &Self::Range<'a>, _keep: &dyn Fn(&str) -> bool) -> Self::Range<'a> {
+        range.clone()
+    }
+
+    fn is_wildcard(range: &Self::Range<'_>) -> bool;
+
+    fn format_range(range: &Self::Range<'_>) -> String;
+
+    /// Checks the syntax of every element of a header, without any supported value.
+    fn validate_header(header: &str) -> Result<(), Error>
+    where
+        Self: Sized,
+    {
+        HeaderElements::<Self>::new(header).try_for_each(|elem| elem.map(drop))
+    }
+
+    /// Drops the malformed elements of a header, keeping the other ones as sent.
+    fn sanitize_header(header: &str) -> String
+    where
+        Self: Sized,
+    {
+        // Parsed with the same splitter as `validate_header`, which yields one result per element.
+        split_unquoted(header, ',')
+            .zip(HeaderElements::<Self>::new(header))
+            .filter(|(_, parsed)| parsed.is_ok())
+            .map(|(elem, _)| elem.trim())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Formats a range the same way whatever the case used by the client, for
+    /// [`NegotiationType::canonicalize_header`].
+    fn format_canonical(range: &Self::Range<'_>) -> String {
+        Self::format_range(range).to_ascii_lowercase()
+    }
+
+    /// Re-serializes a header in canonical form, e.g. to build cache keys: elements sorted by
+    /// quality then precedence, case and whitespace normalized, and duplicates merged into the
+    /// one with the highest quality.
+    fn canonicalize_header(header: &str) -> Result<String, Error>
+    where
+        Self: Sized,
+    {
+        let ranges = parse_and_sort_header(HeaderElements::<Self>::new(header), None)?;
+        let mut elements = Vec::<(String, Quality)>::with_capacity(ranges.len());
+        for (range, q) in &ranges {
+            let range = Self::format_canonical(range);
+            if elements.iter().all(|(r, _q)| *r != range) {
+                elements.push((range, *q));
+            }
+        }
+        Ok(elements
+            .into_iter()
+            .map(|(range, q)| match q {
+                Quality::ONE => range,
+                q => format!("{range};q={q}"),
+            })
+            .collect::<Vec<_>>()
+            .join(", "))
+    }
+
+    /// A key shared by a range and every supported value it can match, when matching it is a
+    /// plain comparison (e.g. `application/json`). Ranges with a key are looked up in an index
+    /// instead of being compared to every supported value.
+    fn range_key(_range: &Self::Range<'_>) -> Option<String> {
+        None
+    }
+
+    /// The key of a supported value only matched by ranges with the same key, see
+    /// [`NegotiationType::range_key`].
+    fn supported_key(_supported: &Self::Parsed) -> Option<String> {
+        None
+    }
+
+    #[cfg(feature = "http")]
     fn associated_header() -> http::header::HeaderName;
 }
 
+#[derive(Clone, PartialEq, Debug)]
+pub struct Negotiated<'a, T> {
+    pub value: &'a T,
+    pub quality: Quality,
+    pub range: String,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Entry<P, T> {
+    pub(crate) parsed: P,
+    pub(crate) value: T,
+    pub(crate) source: String,
+    pub(crate) weight: Quality,
+    pub(crate) availability: Availability,
+}
+
+impl<P, T> Entry<P, T> {
+    // Disabled entries and the ones unavailable at `now` are never selected.
+    pub(crate) fn is_selectable(&self, now: Option<SystemTime>) -> bool {
+        self.weight > Quality::ZERO && now.is_none_or(|now| self.availability.contains(now))
+    }
+}
+
+impl<P, T: AsNegotiationStr> Entry<P, T> {
+    pub(crate) fn new(parsed: P, value: T) -> Self {
+        Self {
+            parsed,
+            source: value.as_str().to_owned(),
+            value,
+            weight: Quality::ONE,
+            availability: Availability::always(),
+        }
+    }
+}
+
+// A selectable entry along with its position in the supported list and the header range scoring
+// it.
+#[derive(Debug)]
+pub(crate) struct Match<'a, P, T> {
+    pub(crate) index: usize,
+    pub(crate) entry: &'a Entry<P, T>,
+    pub(crate) range: usize,
+    pub(crate) quality: Quality,
+}
+
+impl<P, T> Clone for Match<'_, P, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P, T> Copy for Match<'_, P, T> {}
+
+// A value selected by the negotiation: a supported entry, or the default.
+#[derive(Debug)]
+pub(crate) struct Selected<'a, P, T> {
+    // The position of the value in the supported list, which the explicit default may be missing
+    // from.
+    pub(crate) index: Option<usize>,
+    pub(crate) value: &'a T,
+    pub(crate) parsed: Option<&'a P>,
+    pub(crate) source: &'a str,
+    // The header range scoring the value and its quality, `None` when the default is selected
+    // because nothing matches.
+    pub(crate) range: Option<(usize, Quality)>,
+    pub(crate) default: bool,
+}
+
+impl<P, T> Clone for Selected<'_, P, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P, T> Copy for Selected<'_, P, T> {}
+
+impl<P, T> Selected<'_, P, T> {
+    fn quality(&self) -> Quality {
+        self.range.map_or(Quality::ZERO, |(_, quality)| quality)
+    }
+}
+
+impl<'a, P, T> From<Match<'a, P, T>> for Selected<'a, P, T> {
+    fn from(selected: Match<'a, P, T>) -> Self {
+        Self {
+            index: Some(selected.index),
+            value: &selected.entry.value,
+            parsed: Some(&selected.entry.parsed),
+            source: &selected.entry.source,
+            range: Some((selected.range, selected.quality)),
+            default: false,
+        }
+    }
+}
+
+// An explicit default value along with its parsed form, when it parses, and its source.
 #[derive(Clone, Debug)]
+pub(crate) struct DefaultValue<P, T> {
+    pub(crate) value: T,
+    pub(crate) parsed: Option<P>,
+    pub(crate) source: String,
+}
+
+impl<P, T: AsNegotiationStr> DefaultValue<P, T> {
+    pub(crate) fn new(value: T, parsed: Option<P>) -> Self {
+        Self {
+            source: value.as_str().to_owned(),
+            parsed,
+            value,
+        }
+    }
+}
+
+// The supported table and default are shared between clones, mutations copy them on write.
+#[derive(Debug)]
 pub struct Negotiator<N: NegotiationType, T> {
-    supported: Vec<(N::Parsed, T)>,
+    supported: Arc<Supported<N::Parsed, T>>,
+    default: Option<Arc<DefaultValue<N::Parsed, T>>>,
+    // Alias and target pairs, a range matching the alias matches the entries equal to the target.
+    aliases: Arc<[(N::Parsed, N::Parsed)]>,
+    // Fallback chains, each value parsed along with its source to be retried as a range.
+    #[allow(clippy::type_complexity)]
+    fallbacks: Arc<[Vec<(N::Parsed, String)>]>,
+    options: Options,
+    clock: Option<Arc<dyn Clock>>,
+}
+
+impl<N: NegotiationType, T> Clone for Negotiator<N, T> {
+    fn clone(&self) -> Self {
+        Self {
+            supported: Arc::clone(&self.supported),
+            default: self.default.clone(),
+            aliases: Arc::clone(&self.aliases),
+            fallbacks: Arc::clone(&self.fallbacks),
+            options: self.options.clone(),
+            clock: self.clock.clone(),
+        }
+    }
 }
 
 impl<N, T> Negotiator<N, T>
@@ -91,8 +481,689 @@ where
         self.supported.is_empty()
     }
 
+    pub fn supported(&self) -> impl Iterator<Item = &T> {
+        self.supported.iter().map(|entry| &entry.value)
+    }
+
+    pub fn supported_parsed(&self) -> impl Iterator<Item = (&N::Parsed, &T)> {
+        self.supported
+            .iter()
+            .map(|entry| (&entry.parsed, &entry.value))
+    }
+
     pub fn unwrap_first(&self) -> &T {
-        &self.supported[0].1
+        &self.supported[0].value
+    }
+
+    /// Returns the first supported value parsing to the same form as `value`, regardless of
+    /// parameter order. Unparsable values are never supported.
+    pub fn get(&self, value: &str) -> Option<&T>
+    where
+        N::Parsed: PartialEq,
+    {
+        let parsed = self.options.parse_supported::<N, _>(&value).ok()?;
+        self.supported
+            .iter()
+            .find(|entry| entry.parsed == parsed)
+            .map(|entry| &entry.value)
+    }
+
+    pub fn default_value(&self) -> Option<&T> {
+        self.default_selected().map(|selected| selected.value)
+    }
+
+    /// Sets the value selected when the request accepts anything, instead of the first supported
+    /// one, and when nothing matches.
+    pub fn with_default(mut self, value: T) -> Self
+    where
+        T: AsNegotiationStr,
+    {
+        let parsed = self.options.parse_supported::<N, _>(&value).ok();
+        self.default = Some(Arc::new(DefaultValue::new(value, parsed)));
+        self
+    }
+
+    // The explicit default, otherwise the first selectable supported value, selected without a
+    // range.
+    fn default_selected(&self) -> Option<Selected<'_, N::Parsed, T>> {
+        let now = self.now();
+        match &self.default {
+            Some(default) => Some(self.select_default(default, None)),
+            None => self
+                .supported
+                .iter()
+                .enumerate()
+                .find(|(_, entry)| entry.is_selectable(now))
+                .map(|(index, entry)| Selected {
+                    index: Some(index),
+                    value: &entry.value,
+                    parsed: Some(&entry.parsed),
+                    source: &entry.source,
+                    range: None,
+                    default: true,
+                }),
+        }
+    }
+
+    /// Replaces the value associated with each supported entry (and the default) without parsing
+    /// them again.
+    pub fn map<U, F>(self, mut f: F) -> Negotiator<N, U>
+    where
+        F: FnMut(T) -> U,
+        N::Parsed: Clone,
+        T: Clone,
+    {
+        Negotiator {
+            supported: Arc::new(
+                Arc::unwrap_or_clone(self.supported)
+                    .into_iter()
+                    .map(|entry| Entry {
+                        parsed: entry.parsed,
+                        value: f(entry.value),
+                        source: entry.source,
+                        weight: entry.weight,
+                        availability: entry.availability,
+                    })
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            default: self.default.map(|default| {
+                let default = Arc::unwrap_or_clone(default);
+                Arc::new(DefaultValue {
+                    value: f(default.value),
+                    parsed: default.parsed,
+                    source: default.source,
+                })
+            }),
+            aliases: self.aliases,
+            fallbacks: self.fallbacks,
+            options: self.options,
+            clock: self.clock,
+        }
+    }
+
+    /// Negotiates on `header` instead of the one associated with the negotiation type.
+    #[cfg(feature = "http")]
+    pub fn with_header(mut self, header: http::header::HeaderName) -> Self {
+        self.options.header = Some(header);
+        self
+    }
+
+    #[cfg(feature = "http")]
+    pub fn header_name(&self) -> http::header::HeaderName {
+        self.options
+            .header
+            .clone()
+            .unwrap_or_else(N::associated_header)
+    }
+
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Checks every line of a supported list, skipping blank lines and `#` comments, and returns
+    /// the index of each invalid one along with its error.
+    pub fn validate_config<'a, I>(lines: I) -> Vec<(usize, Error)>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| (i, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|(i, line)| N::parse_elem(&line).err().map(|err| (i, err)))
+            .collect()
+    }
+
+    /// Checks a header against the negotiator's parsing options (limits, strict quality and
+    /// wildcard policy), whatever the lenient option.
+    pub fn validate_header(&self, header: &str) -> Result<(), Error> {
+        self.parse_header_skipping(header, None).map(drop)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(kind = ?N::KIND))
+    )]
+    pub fn negotiate(&self, header: &str) -> Result<Option<&T>, Error> {
+        let selected = self
+            .parse_header(header)
+            .and_then(|ranges| self.select(&ranges));
+        self.report_selected(Some(header), selected.as_ref().copied());
+        Ok(selected?.map(|selected| selected.value))
+    }
+
+    /// Negotiates against a raw header field. Fields which are not UTF-8 are decoded as latin-1,
+    /// as allowed for `obs-text`, so high bytes in parameter values do not fail the negotiation.
+    pub fn negotiate_bytes(&self, header: &[u8]) -> Result<Option<&T>, Error> {
+        self.negotiate(&decode_header(header))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(kind = ?N::KIND))
+    )]
+    /// The default selected because nothing matches comes with a zero quality.
+    pub fn negotiate_with_quality(&self, header: &str) -> Result<Option<(&T, Quality)>, Error> {
+        let selected = self
+            .parse_header(header)
+            .and_then(|ranges| self.select(&ranges));
+        self.report_selected(Some(header), selected.as_ref().copied());
+        Ok(selected?.map(|selected| (selected.value, selected.quality())))
+    }
+
+    /// Parses a header once for [`Negotiator::negotiate_parsed`], following the negotiator's
+    /// limits and parsing options, lenient one included.
+    pub fn parse_shared<'a>(&self, header: &'a str) -> Result<ParsedHeader<'a, N>, Error> {
+        let elements = self.header_elements(header);
+        match self.options.lenient {
+            true => ParsedHeader::parse_lenient(elements).map(|(header, _)| header),
+            false => ParsedHeader::parse(elements),
+        }
+    }
+
+    /// Negotiates against a header parsed once for several negotiators. The negotiator's lenient
+    /// option does not apply, it is chosen when parsing.
+    pub fn negotiate_parsed(&self, header: &ParsedHeader<'_, N>) -> Result<Option<&T>, Error> {
+        self.check_wildcards(&header.ranges)?;
+        self.check_params(&header.ranges)?;
+        Ok(self.select(&header.ranges)?.map(|selected| selected.value))
+    }
+
+    /// Like [`Negotiator::negotiate_with_quality`], also returning the header range that matched
+    /// the selected value, empty for the default selected because nothing matches.
+    pub fn negotiate_detailed(&self, header: &str) -> Result<Option<Negotiated<'_, T>>, Error> {
+        let selected = self.parse_header(header).and_then(|ranges| {
+            Ok(self.select(&ranges)?.map(|selected| {
+                let range = selected.range.map(|(i, _)| N::format_range(&ranges[i].0));
+                (selected, range.unwrap_or_default())
+            }))
+        });
+        self.report_selected(
+            Some(header),
+            selected.as_ref().map(|s| s.as_ref().map(|(s, _)| *s)),
+        );
+        Ok(selected?.map(|(selected, range)| Negotiated {
+            value: selected.value,
+            quality: selected.quality(),
+            range,
+        }))
+    }
+
+    /// Negotiates while skipping the malformed elements of the header, whatever the lenient
+    /// option, and returns the index and error of each skipped element along with the selection.
+    #[allow(clippy::type_complexity)]
+    pub fn negotiate_lenient(
+        &self,
+        header: &str,
+    ) -> Result<(Option<&T>, Vec<(usize, Error)>), Error> {
+        let mut skipped = Vec::new();
+        let selected = self
+            .parse_header_skipping(header, Some(&mut skipped))
+            .and_then(|ranges| self.select(&ranges));
+        self.report_selected(Some(header), selected.as_ref().copied());
+        Ok((selected?.map(|selected| selected.value), skipped))
+    }
+
+    /// Like [`Negotiator::negotiate`], failing with [`Error::NotAcceptable`] when no supported
+    /// value matches.
+    pub fn negotiate_strict(&self, header: &str) -> Result<&T, Error> {
+        self.negotiate(header)?.ok_or(Error::NotAcceptable)
+    }
+
+    /// Negotiates against an optional header, an absent one accepting anything and selecting the
+    /// default value.
+    pub fn negotiate_opt(&self, header: Option<&str>) -> Result<Option<&T>, Error>
+    where
+        T: AsNegotiationStr,
+    {
+        let Some(header) = header else {
+            let default = self.default_value();
+            self.report(None, Ok(default.map(T::as_str)), None, true);
+            return Ok(default);
+        };
+        self.negotiate(header)
+    }
+
+    /// Negotiates against an optional header, falling back on the default value when nothing
+    /// matches. Only fails on an invalid header, a strict negotiator or an empty one.
+    pub fn negotiate_or_first(&self, header: Option<&str>) -> Result<&T, Error>
+    where
+        T: AsNegotiationStr,
+    {
+        let selected = match header {
+            Some(header) => self
+                .parse_header(header)
+                .and_then(|ranges| self.select(&ranges)),
+            None => Ok(None),
+        };
+        let selected = selected.and_then(|selected| {
+            selected
+                .or_else(|| self.default_selected())
+                .ok_or(Error::NotAcceptable)
+        });
+        self.report_selected(header, selected.as_ref().map(|s| Some(*s)));
+        selected.map(|selected| selected.value)
+    }
+
+    /// Negotiates against a header split across several fields, which are combined as a single
+    /// comma separated list.
+    pub fn negotiate_multi<'a, I>(&self, headers: I) -> Result<Option<&T>, Error>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        self.negotiate(&combine_fields(headers))
+    }
+
+    /// Negotiates against every field of the associated header, read with [`combined_header`]. A
+    /// missing header accepts anything and selects the default value.
+    #[cfg(feature = "http")]
+    pub fn negotiate_from_headers(&self, headers: &http::HeaderMap) -> Result<Option<&T>, Error>
+    where
+        T: AsNegotiationStr,
+    {
+        self.negotiate_opt(combined_header(headers, self.header_name()).as_deref())
+    }
+
+    pub fn negotiate_all(&self, header: &str) -> Result<Vec<&T>, Error> {
+        let ranges = self.parse_header(header)?;
+        Ok(self
+            .rank(&ranges)
+            .into_iter()
+            .map(|ranked| &ranked.entry.value)
+            .collect())
+    }
+
+    /// Like [`Negotiator::negotiate_all`], also returning the quality the client gave to each
+    /// value, e.g. to measure the demand for values only selected as a last resort.
+    pub fn negotiate_all_with_quality(&self, header: &str) -> Result<Vec<(&T, Quality)>, Error> {
+        let ranges = self.parse_header(header)?;
+        Ok(self
+            .rank(&ranges)
+            .into_iter()
+            .map(|ranked| (&ranked.entry.value, ranked.quality))
+            .collect())
+    }
+
+    // Pairs every selectable entry with the header range scoring it, then orders them by client
+    // quality, server weight and range position.
+    fn rank<'a>(&'a self, ranges: &[(N::Range<'_>, Quality)]) -> Vec<Match<'a, N::Parsed, T>> {
+        let ranges = self.known_ranges(ranges);
+        let mut matches = Vec::new();
+        self.for_each_match(&ranges, |selected| matches.push(selected));
+        matches.sort_by(|m1, m2| self.cmp_matches(&ranges, m1, m2));
+        matches
+    }
+
+    // Tracks the best match while visiting them, in a single pass without sorting the header.
+    fn best<'a>(&'a self, ranges: &[(N::Range<'_>, Quality)]) -> Option<Match<'a, N::Parsed, T>> {
+        self.best_by(ranges, self.candidates(ranges), |entry, i| {
+            self.range_matches(&entry.parsed, &ranges[i].0)
+        })
+    }
+
+    // Like `best`, among the `candidates` entries or all of them, with `matches` telling whether
+    // an entry matches the range at an index.
+    pub(crate) fn best_by<'a>(
+        &'a self,
+        ranges: &[(N::Range<'_>, Quality)],
+        candidates: Option<Vec<usize>>,
+        matches: impl Fn(&Entry<N::Parsed, T>, usize) -> bool,
+    ) -> Option<Match<'a, N::Parsed, T>> {
+        let mut best = None;
+        self.for_each_match_by(ranges, candidates, matches, |selected| match &best {
+            Some(b) if self.cmp_matches(ranges, &selected, b) != Ordering::Less => {}
+            _ => best = Some(selected),
+        });
+        best
+    }
+
+    // Visits every selectable entry in supported order along with the range scoring it. The
+    // ranges may be in any order.
+    fn for_each_match<'a>(
+        &'a self,
+        ranges: &[(N::Range<'_>, Quality)],
+        f: impl FnMut(Match<'a, N::Parsed, T>),
+    ) {
+        let candidates = self.candidates(ranges);
+        self.for_each_match_by(
+            ranges,
+            candidates,
+            |entry, i| self.range_matches(&entry.parsed, &ranges[i].0),
+            f,
+        );
+    }
+
+    // The entries sharing a key with the ranges, `None` when every entry must be tried.
+    fn candidates(&self, ranges: &[(N::Range<'_>, Quality)]) -> Option<Vec<usize>> {
+        // Suffixes and aliases match across keys.
+        match self.options.suffix_matching || !self.aliases.is_empty() {
+            true => None,
+            false => self.supported.candidates::<N>(ranges),
+        }
+    }
+
+    fn for_each_match_by<'a>(
+        &'a self,
+        ranges: &[(N::Range<'_>, Quality)],
+        candidates: Option<Vec<usize>>,
+        matches: impl Fn(&Entry<N::Parsed, T>, usize) -> bool,
+        mut f: impl FnMut(Match<'a, N::Parsed, T>),
+    ) {
+        let now = self.now();
+        let (mut indexed, mut all);
+        let entries: &mut dyn Iterator<Item = usize> = match candidates {
+            Some(candidates) => {
+                indexed = candidates.into_iter();
+                &mut indexed
+            }
+            None => {
+                all = 0..self.supported.len();
+                &mut all
+            }
+        };
+        for (index, entry) in entries
+            .map(|i| (i, &self.supported[i]))
+            .filter(|(_, entry)| entry.is_selectable(now))
+        {
+            // The most specific matching range decides whether the entry is excluded by q=0,
+            // while the first one in quality order scores it with `Scoring::FirstMatch`.
+            let (mut most_specific, mut first) = (None::<usize>, None::<usize>);
+            for (i, (r, q)) in ranges.iter().enumerate() {
+                if !matches(entry, i) {
+                    continue;
+                }
+                if most_specific.is_none_or(|s| {
+                    let (sr, sq) = &ranges[s];
+                    N::precedence(r)
+                        .cmp(&N::precedence(sr))
+                        .then_with(|| q.cmp(sq))
+                        .is_gt()
+                }) {
+                    most_specific = Some(i);
+                }
+                if first.is_none_or(|f| cmp_ranges::<N>(ranges, i, f).is_lt()) {
+                    first = Some(i);
+                }
+            }
+            let (Some(most_specific), Some(first)) = (most_specific, first) else {
+                continue;
+            };
+            if ranges[most_specific].1 == Quality::ZERO {
+                continue;
+            }
+            let i = match self.options.scoring {
+                Scoring::Rfc9110 => most_specific,
+                Scoring::FirstMatch => first,
+            };
+            f(Match {
+                index,
+                entry,
+                range: i,
+                quality: ranges[i].1,
+            });
+        }
+    }
+
+    fn cmp_matches(
+        &self,
+        ranges: &[(N::Range<'_>, Quality)],
+        m1: &Match<'_, N::Parsed, T>,
+        m2: &Match<'_, N::Parsed, T>,
+    ) -> Ordering {
+        let (e1, i1, q1) = (m1.entry, &m1.range, &m1.quality);
+        let (e2, i2, q2) = (m2.entry, &m2.range, &m2.quality);
+        let order = match self.options.weighting {
+            Weighting::ClientFirst => q2.cmp(q1).then_with(|| e2.weight.cmp(&e1.weight)),
+            Weighting::Multiplicative => {
+                let product =
+                    |q: &Quality, w: Quality| q.thousandths() as u32 * w.thousandths() as u32;
+                product(q2, e2.weight).cmp(&product(q1, e1.weight))
+            }
+        };
+        match self.options.tie_break {
+            TieBreak::ClientOrder => order.then_with(|| cmp_ranges::<N>(ranges, *i1, *i2)),
+            TieBreak::ServerOrder => order,
+            TieBreak::MostSpecific => order
+                .then_with(|| N::specificity(&e2.parsed).cmp(&N::specificity(&e1.parsed)))
+                .then_with(|| cmp_ranges::<N>(ranges, *i1, *i2)),
+            TieBreak::Custom(cmp) => order.then_with(|| cmp(&e1.source, &e2.source)),
+        }
+    }
+
+    // Selects the best entry, or the explicit default when the header leaves the choice to the
+    // server by only accepting ranges matching anything, e.g. `*/*`, or when nothing matches. The
+    // default is never selected when a q=0 range excludes it.
+    pub(crate) fn select<'a>(
+        &'a self,
+        ranges: &[(N::Range<'_>, Quality)],
+    ) -> Result<Option<Selected<'a, N::Parsed, T>>, Error> {
+        let default = self.default.as_deref().filter(|default| {
+            default
+                .parsed
+                .as_ref()
+                .is_none_or(|parsed| !self.is_excluded(&self.known_ranges(ranges), parsed))
+        });
+        if let Some(default) = default {
+            let accepted = (0..ranges.len()).filter(|&i| ranges[i].1 > Quality::ZERO);
+            if accepted.clone().next().is_some()
+                && accepted
+                    .clone()
+                    .all(|i| N::precedence(&ranges[i].0) == (0, 0))
+            {
+                let range = accepted.min_by(|&i1, &i2| cmp_ranges::<N>(ranges, i1, i2));
+                return Ok(Some(
+                    self.select_default(default, range.map(|i| (i, ranges[i].1))),
+                ));
+            }
+        }
+        Ok(match self.select_entry(ranges)? {
+            Some(selected) => Some(selected.into()),
+            None => default.map(|default| self.select_default(default, None)),
+        })
+    }
+
+    fn select_default<'a>(
+        &'a self,
+        default: &'a DefaultValue<N::Parsed, T>,
+        range: Option<(usize, Quality)>,
+    ) -> Selected<'a, N::Parsed, T> {
+        Selected {
+            index: self
+                .supported
+                .iter()
+                .position(|entry| entry.source == default.source),
+            value: &default.value,
+            parsed: default.parsed.as_ref(),
+            source: &default.source,
+            range,
+            default: true,
+        }
+    }
+
+    fn report_selected(
+        &self,
+        header: Option<&str>,
+        selected: Result<Option<Selected<'_, N::Parsed, T>>, &Error>,
+    ) {
+        let selected = selected.map(|s| s.map(|s| (s.source, s.default, s.range)));
+        self.report(
+            header,
+            selected.map(|s| s.map(|(source, _, _)| source)),
+            match selected {
+                Ok(Some((_, false, Some((_, quality))))) => Some(quality),
+                _ => None,
+            },
+            matches!(selected, Ok(Some((_, true, _)))),
+        );
+    }
+
+    // Only looks up the clock when an entry is gated by availability.
+    fn now(&self) -> Option<SystemTime> {
+        self.supported
+            .iter()
+            .any(|entry| !entry.availability.is_always())
+            .then(|| match &self.clock {
+                Some(clock) => clock.now(),
+                None => SystemClock.now(),
+            })
+    }
+
+    fn range_matches(&self, parsed: &N::Parsed, range: &N::Range<'_>) -> bool {
+        if self.options.wildcard == WildcardPolicy::Ignore && N::is_wildcard(range) {
+            return false;
+        }
+        N::matches_with(parsed, range, self.options.matching)
+            || (self.options.suffix_matching
+                && N::matches_suffix(parsed, range, self.options.matching))
+            || (!N::is_wildcard(range)
+                && self.aliases.iter().any(|(alias, target)| {
+                    target == parsed && N::matches_with(alias, range, self.options.matching)
+                }))
+    }
+
+    // Whether the most specific range matching `parsed` has a zero quality, which excludes it as
+    // in `for_each_match_by`.
+    fn is_excluded(&self, ranges: &[(N::Range<'_>, Quality)], parsed: &N::Parsed) -> bool {
+        ranges
+            .iter()
+            .filter(|(r, _)| self.range_matches(parsed, r))
+            .max_by(|(r1, q1), (r2, q2)| {
+                N::precedence(r1)
+                    .cmp(&N::precedence(r2))
+                    .then_with(|| q1.cmp(q2))
+            })
+            .is_some_and(|(_, q)| *q == Quality::ZERO)
+    }
+
+    fn select_entry(
+        &self,
+        ranges: &[(N::Range<'_>, Quality)],
+    ) -> Result<Option<Match<'_, N::Parsed, T>>, Error> {
+        let ranges = self.known_ranges(ranges);
+        match self.best(&ranges).or_else(|| self.fallback(&ranges)) {
+            Some(selected) => Ok(Some(selected)),
+            None if self.options.strict => Err(Error::NotAcceptable),
+            None => Ok(None),
+        }
+    }
+
+    // Walks the ranges in quality order and retries each one with the rest of the first chain
+    // covering it, scoring the selection with the range that fell back. The entries matched by a
+    // q=0 range stay excluded.
+    fn fallback<'a>(
+        &'a self,
+        ranges: &[(N::Range<'_>, Quality)],
+    ) -> Option<Match<'a, N::Parsed, T>> {
+        let excluded = ranges.iter().filter(|(_, q)| *q == Quality::ZERO);
+        let mut order = (0..ranges.len())
+            .filter(|&i| ranges[i].1 > Quality::ZERO && !N::is_wildcard(&ranges[i].0))
+            .collect::<Vec<_>>();
+        order.sort_by(|&i1, &i2| cmp_ranges::<N>(ranges, i1, i2));
+        for i in order {
+            let (range, q) = &ranges[i];
+            let Some(chain) = self.fallbacks.iter().find_map(|chain| {
+                let start = chain.iter().position(|(parsed, _)| {
+                    N::matches_with(parsed, range, self.options.matching)
+                })?;
+                Some(&chain[start..])
+            }) else {
+                continue;
+            };
+            for (_, fallback) in chain {
+                let Ok((fallback, _)) = N::parse_header_elem(fallback) else {
+                    continue;
+                };
+                let retry = [(fallback, *q)];
+                let mut best = None;
+                self.for_each_match(&retry, |selected| {
+                    if excluded
+                        .clone()
+                        .any(|(r, _)| self.range_matches(&selected.entry.parsed, r))
+                    {
+                        return;
+                    }
+                    match &best {
+                        Some(b) if self.cmp_matches(&retry, &selected, b) != Ordering::Less => {}
+                        _ => best = Some(selected),
+                    }
+                });
+                if let Some(best) = best {
+                    return Some(Match {
+                        range: i,
+                        quality: *q,
+                        ..best
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn parse_header<'a>(&self, header: &'a str) -> Result<Vec<(N::Range<'a>, Quality)>, Error> {
+        let mut skipped = Vec::new();
+        self.parse_header_skipping(header, self.options.lenient.then_some(&mut skipped))
+    }
+
+    fn parse_header_skipping<'a>(
+        &self,
+        header: &'a str,
+        skipped: Option<&mut Vec<(usize, Error)>>,
+    ) -> Result<Vec<(N::Range<'a>, Quality)>, Error> {
+        let ranges = parse_elements(self.header_elements(header), skipped)?;
+        self.check_wildcards(&ranges)?;
+        self.check_params(&ranges)?;
+        Ok(ranges)
+    }
+
+    fn header_elements<'a>(&self, header: &'a str) -> HeaderElements<'a, N> {
+        HeaderElements::with_limits(header, self.options.limits)
+            .strict_quality(self.options.strict_quality)
+            .browser_quirks(self.options.browser_quirks)
+    }
+
+    fn check_params(&self, ranges: &[(N::Range<'_>, Quality)]) -> Result<(), Error> {
+        if self.options.unknown_params == UnknownParams::Error
+            && ranges
+                .iter()
+                .any(|(r, _q)| N::retain_params(r, &|name| self.is_known_param(name)) != *r)
+        {
+            return Err(Error::UnknownParameter);
+        }
+        Ok(())
+    }
+
+    // Drops the parameters no supported value declares when they are ignored by policy. Indexes
+    // into the returned ranges are the same as into `ranges`.
+    fn known_ranges<'a, 'r>(
+        &self,
+        ranges: &'a [(N::Range<'r>, Quality)],
+    ) -> Cow<'a, [(N::Range<'r>, Quality)]> {
+        match self.options.unknown_params {
+            UnknownParams::Ignore => ranges
+                .iter()
+                .map(|(r, q)| (N::retain_params(r, &|name| self.is_known_param(name)), *q))
+                .collect(),
+            _ => Cow::Borrowed(ranges),
+        }
+    }
+
+    fn is_known_param(&self, name: &str) -> bool {
+        self.supported
+            .iter()
+            .any(|entry| N::has_param(&entry.parsed, name))
+    }
+
+    // Wildcards ignored by policy are skipped while ranking.
+    fn check_wildcards(&self, ranges: &[(N::Range<'_>, Quality)]) -> Result<(), Error> {
+        if self.options.wildcard == WildcardPolicy::Reject
+            && ranges.iter().any(|(r, _q)| N::is_wildcard(r))
+        {
+            return Err(Error::InvalidWildcard);
+        }
+        Ok(())
     }
 }
 
@@ -101,50 +1172,339 @@ where
     N: NegotiationType,
     T: AsNegotiationStr,
 {
+    pub fn builder() -> NegotiatorBuilder<N, T> {
+        NegotiatorBuilder::new()
+    }
+
     pub fn new<I>(iter: I) -> Result<Self, Error>
     where
         I: IntoIterator<Item = T>,
+    {
+        Self::new_weighted(iter.into_iter().map(|m| (m, Quality::ONE)))
+    }
+
+    /// Builds a negotiator where each supported value carries a server preference weight
+    /// between 0 and 1, either a [`Quality`] or an `f32`. Weights order the variants the client
+    /// finds equally acceptable, and a weight of 0 disables the variant.
+    pub fn new_weighted<I, W>(iter: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (T, W)>,
+        W: TryInto<Quality>,
+        Error: From<W::Error>,
     {
         Ok(Self {
-            supported: iter
-                .into_iter()
-                .map(|m| Ok((N::parse_elem(&m)?, m)))
-                .collect::<Result<_, _>>()?,
+            supported: Arc::new(
+                iter.into_iter()
+                    .map(|(m, weight)| {
+                        Ok(Entry {
+                            weight: weight.try_into().map_err(Error::from)?,
+                            ..Entry::new(N::parse_elem(&m)?, m)
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?
+                    .into(),
+            ),
+            default: None,
+            aliases: Arc::new([]),
+            fallbacks: Arc::new([]),
+            options: Options::default(),
+            clock: None,
         })
     }
+}
 
-    pub fn negotiate(&self, header: &str) -> Result<Option<&T>, Error> {
-        N::parse_and_negotiate_header(&self.supported, header)
+impl<'a, N: NegotiationType> Negotiator<N, Cow<'a, str>> {
+    /// Builds a negotiator borrowing its supported values instead of copying them. Owned values
+    /// can still be pushed later on, and [`Negotiator::map`] with [`Cow::into_owned`] detaches it
+    /// from `'a`.
+    pub fn borrowed(values: &[&'a str]) -> Result<Self, Error> {
+        Self::new(values.iter().copied().map(Cow::Borrowed))
     }
 }
 
-fn match_first<'a, 'b, S, T, H, F, I, J>(supported: I, from_header: J, mut f: F) -> Option<&'a T>
+impl<N, T> Negotiator<N, T>
 where
-    S: 'a,
-    H: 'b + ?Sized,
-    I: IntoIterator<Item = &'a (S, T)> + Clone,
-    J: IntoIterator<Item = &'b H>,
-    F: FnMut(&'a S, &'b H) -> bool,
+    N: NegotiationType,
+    N::Parsed: Clone,
+    T: AsNegotiationStr + Clone,
 {
-    from_header.into_iter().find_map(|h| {
-        supported
-            .clone()
+    /// Restricts the variants whose string is `value` to the given time window, evaluated
+    /// against the negotiator's clock at negotiation time.
+    pub fn with_availability(mut self, value: &str, availability: Availability) -> Self {
+        Arc::make_mut(&mut self.supported)
+            .iter_mut()
+            .filter(|entry| *entry.source == *value)
+            .for_each(|entry| entry.availability = availability);
+        self
+    }
+
+    /// Appends every value following the duplicate policy, leaving the negotiator untouched if
+    /// any of them is invalid or rejected as a duplicate.
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = T>,
+        N::Parsed: PartialEq,
+    {
+        let mut supported = Vec::clone(&self.supported);
+        for value in iter {
+            let entry = Entry::new(self.options.parse_supported::<N, _>(&value)?, value);
+            self.options.duplicates.push(&mut supported, entry)?;
+        }
+        self.supported = Arc::new(supported.into());
+        Ok(())
+    }
+
+    /// Appends the values of `other` after the ones of `self`, whose default, options and clock
+    /// are kept.
+    pub fn merge(mut self, other: Self, duplicates: DuplicatePolicy) -> Result<Self, Error>
+    where
+        N::Parsed: PartialEq,
+    {
+        let supported = Arc::make_mut(&mut self.supported);
+        for entry in Arc::unwrap_or_clone(other.supported) {
+            duplicates.push(supported, entry)?;
+        }
+        if self.default.is_none() {
+            self.default = other.default;
+        }
+        Ok(self)
+    }
+
+    /// Appends a value following the duplicate policy.
+    pub fn push(&mut self, value: T) -> Result<(), Error>
+    where
+        N::Parsed: PartialEq,
+    {
+        self.insert(self.supported.len(), value)
+    }
+
+    /// Inserts a value following the duplicate policy, a replaced duplicate keeping its position.
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), Error>
+    where
+        N::Parsed: PartialEq,
+    {
+        let entry = Entry::new(self.options.parse_supported::<N, _>(&value)?, value);
+        let supported = Arc::make_mut(&mut self.supported);
+        self.options.duplicates.insert(supported, index, entry)
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        Arc::make_mut(&mut self.supported).remove(index).value
+    }
+
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        Arc::make_mut(&mut self.supported).retain(|entry| f(&entry.value));
+    }
+}
+
+impl<N, T> IntoIterator for Negotiator<N, T>
+where
+    N: NegotiationType,
+    N::Parsed: Clone,
+    T: Clone,
+{
+    type Item = T;
+    type IntoIter = vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Arc::unwrap_or_clone(self.supported)
             .into_iter()
-            .find_map(|(s, v)| f(s, h).then(|| v))
-    })
+            .map(|entry| entry.value)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
 }
 
-fn extract_quality<K, V>(params: &mut BTreeMap<K, V>) -> Result<f32, Error>
+impl<'a, N: NegotiationType, T> IntoIterator for &'a Negotiator<N, T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, N::Parsed, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter(self.supported.iter())
+    }
+}
+
+pub struct Iter<'a, P, T>(slice::Iter<'a, Entry<P, T>>);
+
+impl<'a, P, T> Iterator for Iter<'a, P, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|entry| &entry.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// Negotiates with the behavior of `kind` chosen at runtime, for callers that only know which
+/// header they handle once a request comes in.
+pub fn negotiate_dyn<'a, S>(
+    kind: NegotiationKind,
+    supported: &'a [S],
+    header: &str,
+) -> Result<Option<&'a S>, Error>
 where
-    K: Borrow<str> + Ord,
-    V: Deref<Target = str>,
+    S: AsRef<str>,
 {
-    params
-        .remove("q")
-        .map(|q| {
-            q.parse::<f32>()
-                .map_err(|err| Error::InvalidQuality { source: err })
+    fn negotiate<'a, N: NegotiationType, S: AsRef<str>>(
+        supported: &'a [S],
+        header: &str,
+    ) -> Result<Option<&'a S>, Error> {
+        Ok(Negotiator::<N, _>::new(supported)?
+            .negotiate(header)?
+            .copied())
+    }
+
+    match kind {
+        NegotiationKind::ContentType => negotiate::<ContentTypeNegotiation, _>(supported, header),
+        NegotiationKind::Language => negotiate::<LanguageNegotiation, _>(supported, header),
+        NegotiationKind::Encoding => negotiate::<EncodingNegotiation, _>(supported, header),
+        NegotiationKind::Token => negotiate::<AnyTokenNegotiation, _>(supported, header),
+    }
+}
+
+pub struct ParsedHeader<'a, N: NegotiationType> {
+    ranges: Vec<(N::Range<'a>, Quality)>,
+}
+
+impl<'a, N: NegotiationType> ParsedHeader<'a, N> {
+    /// Parses the elements of a header once for several negotiators, following the limits and
+    /// parsing options `elements` was built with.
+    pub fn parse(elements: HeaderElements<'a, N>) -> Result<Self, Error> {
+        Ok(Self {
+            ranges: parse_and_sort_header(elements, None)?,
         })
-        .transpose()
-        .map(|q| q.unwrap_or(1.))
+    }
+
+    /// Parses the elements, skipping malformed ones and returning the index and error of each of
+    /// them. Only fails when a limit is exceeded.
+    pub fn parse_lenient(
+        elements: HeaderElements<'a, N>,
+    ) -> Result<(Self, Vec<(usize, Error)>), Error> {
+        let mut skipped = Vec::new();
+        let ranges = parse_and_sort_header(elements, Some(&mut skipped))?;
+        Ok((Self { ranges }, skipped))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+/// Combines the fields of a header sent several times into a single comma separated list, as
+/// allowed for list-based headers by RFC 9110. Empty fields are skipped.
+pub fn combine_fields<'a, I>(fields: I) -> Cow<'a, str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut fields = fields.into_iter().filter(|f| !f.trim().is_empty());
+    let Some(first) = fields.next() else {
+        return Cow::Borrowed("");
+    };
+    match fields.next() {
+        Some(second) => Cow::Owned(
+            [first, second]
+                .into_iter()
+                .chain(fields)
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        None => Cow::Borrowed(first),
+    }
+}
+
+/// Reads every field of a header as a single combined value, decoding non UTF-8 bytes as
+/// Latin-1. Returns `None` when the header is absent.
+#[cfg(feature = "http")]
+pub fn combined_header<K>(headers: &http::HeaderMap, name: K) -> Option<Cow<'_, str>>
+where
+    K: http::header::AsHeaderName,
+{
+    let mut fields = headers
+        .get_all(name)
+        .iter()
+        .map(|field| decode_header(field.as_bytes()))
+        .collect::<Vec<_>>();
+    match fields.len() {
+        0 => None,
+        1 => fields.pop(),
+        _ => Some(Cow::Owned(
+            combine_fields(fields.iter().map(AsRef::as_ref)).into_owned(),
+        )),
+    }
+}
+
+pub(crate) fn decode_header(header: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(header) {
+        Ok(header) => Cow::Borrowed(header),
+        Err(_) => Cow::Owned(header.iter().copied().map(char::from).collect()),
+    }
+}
+
+// Malformed elements fail the whole header, unless a list is given to collect them into. Limits
+// are checked before parsing and always fail the header.
+#[allow(clippy::type_complexity)]
+fn parse_elements<'a, N: NegotiationType>(
+    elements: HeaderElements<'a, N>,
+    mut skipped: Option<&mut Vec<(usize, Error)>>,
+) -> Result<Vec<(N::Range<'a>, Quality)>, Error> {
+    let mut ranges = Vec::new();
+    for (i, elem) in elements.enumerate() {
+        match (elem, skipped.as_deref_mut()) {
+            (Ok(range), _) => ranges.push(range),
+            (Err(Error::LimitExceeded), _) => return Err(Error::LimitExceeded),
+            (Err(err), Some(skipped)) => skipped.push((i, err)),
+            (Err(err), None) => return Err(err),
+        }
+    }
+    ranges.extend(N::implicit_range(&ranges));
+    Ok(ranges)
+}
+
+#[allow(clippy::type_complexity)]
+fn parse_and_sort_header<'a, N: NegotiationType>(
+    elements: HeaderElements<'a, N>,
+    skipped: Option<&mut Vec<(usize, Error)>>,
+) -> Result<Vec<(N::Range<'a>, Quality)>, Error> {
+    parse_elements(elements, skipped).map(sort_ranges::<N>)
+}
+
+// Orders ranges by quality then precedence, a repeated range keeping its highest quality.
+#[allow(clippy::type_complexity)]
+fn sort_ranges<'a, N: NegotiationType>(
+    mut ranges: Vec<(N::Range<'a>, Quality)>,
+) -> Vec<(N::Range<'a>, Quality)> {
+    ranges.sort_by(|(r1, q1), (r2, q2)| {
+        q1.cmp(q2)
+            .then_with(|| N::precedence(r1).cmp(&N::precedence(r2)))
+            .reverse()
+    });
+    let mut unique = Vec::<(N::Range<'a>, Quality)>::with_capacity(ranges.len());
+    for (range, q) in ranges {
+        if unique.iter().all(|(r, _q)| *r != range) {
+            unique.push((range, q));
+        }
+    }
+    unique
+}
+
+// Orders two ranges of a header as if it was sorted with `sort_ranges`, without sorting it.
+fn cmp_ranges<N: NegotiationType>(
+    ranges: &[(N::Range<'_>, Quality)],
+    i1: usize,
+    i2: usize,
+) -> Ordering {
+    let ((r1, q1), (r2, q2)) = (&ranges[i1], &ranges[i2]);
+    q2.cmp(q1)
+        .then_with(|| N::precedence(r2).cmp(&N::precedence(r1)))
+        .then_with(|| i1.cmp(&i2))
 }
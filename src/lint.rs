@@ -0,0 +1,272 @@
+use std::{collections::HashSet, fmt, ops::Range};
+
+use crate::{
+    params::{is_token_char, split_unquoted},
+    token::AnyTokenNegotiation,
+    ContentTypeNegotiation, EncodingNegotiation, Error, HeaderParams, LanguageNegotiation,
+    NegotiationKind, NegotiationType, Quality,
+};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum DiagnosticKind {
+    EmptyElement,
+    Malformed(Error),
+    InvalidQuality,
+    InvalidToken,
+    DuplicateMember,
+    SuspiciousWildcard,
+}
+
+impl DiagnosticKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DiagnosticKind::EmptyElement => "empty-element",
+            DiagnosticKind::Malformed(_) => "malformed",
+            DiagnosticKind::InvalidQuality => "invalid-quality",
+            DiagnosticKind::InvalidToken => "invalid-token",
+            DiagnosticKind::DuplicateMember => "duplicate-member",
+            DiagnosticKind::SuspiciousWildcard => "suspicious-wildcard",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            DiagnosticKind::Malformed(_)
+            | DiagnosticKind::InvalidQuality
+            | DiagnosticKind::InvalidToken => Severity::Error,
+            DiagnosticKind::EmptyElement
+            | DiagnosticKind::DuplicateMember
+            | DiagnosticKind::SuspiciousWildcard => Severity::Warning,
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub element: usize,
+    pub span: Range<usize>,
+    pub kind: DiagnosticKind,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match &self.kind {
+            DiagnosticKind::EmptyElement => "empty element".to_owned(),
+            DiagnosticKind::Malformed(err) => err.to_string(),
+            DiagnosticKind::InvalidQuality => {
+                "quality must be between 0 and 1 with at most three decimals".to_owned()
+            }
+            DiagnosticKind::InvalidToken => "invalid character in token".to_owned(),
+            DiagnosticKind::DuplicateMember => "duplicate member".to_owned(),
+            DiagnosticKind::SuspiciousWildcard => "suspicious wildcard".to_owned(),
+        };
+        write!(
+            f,
+            "element {} ({}..{}): {message}",
+            self.element, self.span.start, self.span.end
+        )
+    }
+}
+
+pub fn lint_header(kind: NegotiationKind, header: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut seen = HashSet::new();
+    let mut offset = 0;
+    for (element, raw) in split_unquoted(header, ',').enumerate() {
+        let start = offset + (raw.len() - raw.trim_start().len());
+        let entry = raw.trim();
+        let span = start..start + entry.len();
+        offset += raw.len() + 1;
+
+        let mut push = |kind| {
+            diagnostics.push(Diagnostic {
+                element,
+                span: span.clone(),
+                kind,
+            })
+        };
+        if entry.is_empty() {
+            push(DiagnosticKind::EmptyElement);
+            continue;
+        }
+
+        let range = split_unquoted(entry, ';').next().unwrap_or_default();
+        let mut params = Vec::new();
+        let mut quality = None;
+        for (k, v) in HeaderParams::extensions(&entry[range.len()..]).iter() {
+            match (k.trim(), v.trim()) {
+                (k, v) if k.eq_ignore_ascii_case("q") => quality = Some(v),
+                param => params.push(param),
+            }
+        }
+        let range = range.trim();
+
+        let structure = match kind {
+            NegotiationKind::ContentType => {
+                ContentTypeNegotiation::parse_header_elem(entry).map(drop)
+            }
+            NegotiationKind::Language => LanguageNegotiation::parse_header_elem(entry).map(drop),
+            NegotiationKind::Encoding => EncodingNegotiation::parse_header_elem(entry).map(drop),
+            NegotiationKind::Token => AnyTokenNegotiation::parse_header_elem(entry).map(drop),
+        };
+        match structure {
+            Err(Error::InvalidQuality { .. }) => {}
+            Err(err) => push(DiagnosticKind::Malformed(err)),
+            Ok(()) => {}
+        }
+        if quality.is_some_and(|q| q.parse::<Quality>().is_err()) {
+            push(DiagnosticKind::InvalidQuality);
+        }
+
+        let separator = match kind {
+            NegotiationKind::ContentType => '/',
+            NegotiationKind::Language => '-',
+            NegotiationKind::Encoding | NegotiationKind::Token => ',',
+        };
+        let valid_tokens = range
+            .split(separator)
+            .chain(params.iter().map(|(k, _)| *k))
+            .all(|token| !token.is_empty() && token.chars().all(is_token_char))
+            && params
+                .iter()
+                .all(|(_, v)| v.chars().all(is_token_char) || is_quoted_string(v));
+        if !valid_tokens {
+            push(DiagnosticKind::InvalidToken);
+        }
+
+        let wildcard = match kind {
+            NegotiationKind::ContentType => range.ends_with("/*") && !params.is_empty(),
+            NegotiationKind::Language => range.starts_with("*-"),
+            NegotiationKind::Encoding | NegotiationKind::Token => false,
+        };
+        if wildcard {
+            push(DiagnosticKind::SuspiciousWildcard);
+        }
+
+        let mut key = vec![range.to_ascii_lowercase()];
+        let mut normalized_params = params
+            .iter()
+            .map(|(k, v)| format!("{}={v}", k.to_ascii_lowercase()))
+            .collect::<Vec<_>>();
+        normalized_params.sort();
+        key.extend(normalized_params);
+        if !seen.insert(key) {
+            push(DiagnosticKind::DuplicateMember);
+        }
+    }
+    diagnostics
+}
+
+fn is_quoted_string(value: &str) -> bool {
+    value.len() >= 2 && value.starts_with('"') && value.ends_with('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint_header, Diagnostic, DiagnosticKind, Severity};
+    use crate::{Error, NegotiationKind};
+
+    fn kinds(kind: NegotiationKind, header: &str) -> Vec<(usize, DiagnosticKind)> {
+        lint_header(kind, header)
+            .into_iter()
+            .map(|d| (d.element, d.kind))
+            .collect()
+    }
+
+    #[test]
+    fn clean() {
+        assert!(lint_header(
+            NegotiationKind::ContentType,
+            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"
+        )
+        .is_empty());
+        assert!(lint_header(NegotiationKind::Language, "en-US, fr;q=0.5, *;q=0.1").is_empty());
+        assert!(lint_header(NegotiationKind::Encoding, "gzip, br;q=1.0, *;q=0").is_empty());
+    }
+
+    #[test]
+    fn content_type() {
+        assert_eq!(
+            kinds(
+                NegotiationKind::ContentType,
+                "text/html;q=2, te xt/plain, text/html, */*;level=1, image, \
+                 ,application/json;q=0.1234"
+            ),
+            vec![
+                (0, DiagnosticKind::InvalidQuality),
+                (1, DiagnosticKind::InvalidToken),
+                (2, DiagnosticKind::DuplicateMember),
+                (3, DiagnosticKind::SuspiciousWildcard),
+                (4, DiagnosticKind::Malformed(Error::MissingSeparator('/'))),
+                (5, DiagnosticKind::EmptyElement),
+                (6, DiagnosticKind::InvalidQuality),
+            ]
+        );
+
+        assert_eq!(
+            kinds(NegotiationKind::ContentType, "text/html;q=abc"),
+            vec![(0, DiagnosticKind::InvalidQuality)]
+        );
+        assert_eq!(
+            kinds(
+                NegotiationKind::ContentType,
+                r#"text/html;title="a,b";q=0.5, application/json, text/html;title="x;q=2""#
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn language() {
+        assert_eq!(
+            kinds(
+                NegotiationKind::Language,
+                "en-US, EN-us;q=0.5, *-CH, fr;level=1"
+            ),
+            vec![
+                (1, DiagnosticKind::DuplicateMember),
+                (2, DiagnosticKind::SuspiciousWildcard),
+                (3, DiagnosticKind::Malformed(Error::ParamsNotAllowed)),
+            ]
+        );
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(
+            kinds(NegotiationKind::Encoding, "gzip;q=-1, g@zip, gzip"),
+            vec![
+                (0, DiagnosticKind::InvalidQuality),
+                (1, DiagnosticKind::InvalidToken),
+                (2, DiagnosticKind::DuplicateMember),
+            ]
+        );
+    }
+
+    #[test]
+    fn span() {
+        let diagnostics = lint_header(NegotiationKind::Encoding, "gzip,  br;q=7");
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                element: 1,
+                span: 7..13,
+                kind: DiagnosticKind::InvalidQuality,
+            }]
+        );
+        assert_eq!(diagnostics[0].severity(), Severity::Error);
+        assert_eq!(diagnostics[0].kind.code(), "invalid-quality");
+    }
+}
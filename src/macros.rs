@@ -0,0 +1,379 @@
+use crate::{language_tag::GRANDFATHERED, NegotiationKind};
+
+/// Builds a negotiator over `&'static str` values, checking them at compile time so invalid
+/// entries such as `text/plain;q=1` fail the build instead of panicking at runtime.
+///
+/// ```
+/// use http_negotiator::{negotiator, ContentTypeNegotiation};
+///
+/// negotiator!(static CONTENT: ContentTypeNegotiation = ["application/json", "text/html"]);
+///
+/// let encoding = negotiator!(http_negotiator::EncodingNegotiation, ["br", "gzip"]);
+/// assert_eq!(CONTENT.negotiate("text/*").unwrap(), Some(&"text/html"));
+/// assert_eq!(encoding.negotiate("gzip").unwrap(), Some(&"gzip"));
+/// ```
+///
+/// ```compile_fail
+/// use http_negotiator::{negotiator, ContentTypeNegotiation};
+///
+/// let negotiator = negotiator!(ContentTypeNegotiation, ["text/plain;q=1"]);
+/// ```
+#[macro_export]
+macro_rules! negotiator {
+    ($vis:vis static $name:ident: $negotiation:ty = [$($value:literal),+ $(,)?]) => {
+        $vis static $name: ::std::sync::LazyLock<$crate::Negotiator<$negotiation, &'static str>> =
+            ::std::sync::LazyLock::new(|| $crate::negotiator!($negotiation, [$($value),+]));
+    };
+    ($negotiation:ty, [$($value:literal),+ $(,)?]) => {{
+        $(
+            const _: () = ::std::assert!(
+                $crate::is_valid_supported(
+                    <$negotiation as $crate::NegotiationType>::KIND,
+                    $value,
+                ),
+                ::std::concat!("invalid supported value \"", $value, "\""),
+            );
+        )+
+        $crate::Negotiator::<$negotiation, &'static str>::new([$($value),+])
+            .expect("supported values are checked at compile time")
+    }};
+}
+
+/// Mirrors the validation done when parsing supported values, usable in const contexts.
+#[doc(hidden)]
+pub const fn is_valid_supported(kind: NegotiationKind, value: &str) -> bool {
+    let value = value.as_bytes();
+    match kind {
+        NegotiationKind::ContentType => is_valid_media_type(value),
+        NegotiationKind::Language => is_valid_language_tag(value),
+        NegotiationKind::Encoding | NegotiationKind::Token => {
+            find(value, 0, value.len(), b';').is_none() && !equals(value, 0, value.len(), b"*")
+        }
+    }
+}
+
+const fn is_valid_media_type(value: &[u8]) -> bool {
+    let mut end = match find(value, 0, value.len(), b';') {
+        Some(i) => i,
+        None => value.len(),
+    };
+    let (start, left_end) = trim(value, 0, end);
+    let Some(slash) = find(value, start, left_end, b'/') else {
+        return false;
+    };
+    if find(value, slash + 1, left_end, b'/').is_some()
+        || equals(value, start, slash, b"*")
+        || equals(value, slash + 1, left_end, b"*")
+    {
+        return false;
+    }
+
+    // Parameters are split outside of quoted strings, like `split_unquoted` does.
+    while end < value.len() {
+        let next = find_unquoted(value, end + 1, b';');
+        let (start, param_end) = trim(value, end + 1, next);
+        match find(value, start, param_end, b'=') {
+            Some(eq) if !equals_ignore_case(value, start, eq, b"q") => {}
+            _ => return false,
+        }
+        end = next;
+    }
+    true
+}
+
+// Follows the same steps as `LanguageTag::parse`.
+const fn is_valid_language_tag(value: &[u8]) -> bool {
+    let mut i = 0;
+    while i < GRANDFATHERED.len() {
+        if GRANDFATHERED[i].0.as_bytes().eq_ignore_ascii_case(value) {
+            return true;
+        }
+        i += 1;
+    }
+    let len = value.len();
+    let mut start = 0;
+    while start <= len {
+        let end = subtag_end(value, start);
+        if end == start || end - start > 8 || !all(value, start, end, Class::Alphanumeric) {
+            return false;
+        }
+        start = end + 1;
+    }
+
+    let (mut start, mut end) = (0, subtag_end(value, 0));
+    // A private use tag, e.g. `x-pirate`, has no language.
+    if !equals_ignore_case(value, start, end, b"x") {
+        if end - start < 2 || !all(value, start, end, Class::Alpha) {
+            return false;
+        }
+        let language = end - start;
+        (start, end) = next_subtag(value, end);
+        let mut extlangs = 0;
+        while language <= 3
+            && extlangs < 3
+            && start <= len
+            && end - start == 3
+            && all(value, start, end, Class::Alpha)
+        {
+            (start, end) = next_subtag(value, end);
+            extlangs += 1;
+        }
+        if start <= len && end - start == 4 && all(value, start, end, Class::Alpha) {
+            (start, end) = next_subtag(value, end);
+        }
+        if start <= len
+            && ((end - start == 2 && all(value, start, end, Class::Alpha))
+                || (end - start == 3 && all(value, start, end, Class::Digit)))
+        {
+            (start, end) = next_subtag(value, end);
+        }
+        while start <= len
+            && (end - start >= 5 || (end - start == 4 && value[start].is_ascii_digit()))
+        {
+            (start, end) = next_subtag(value, end);
+        }
+        while start <= len && end - start == 1 && !equals_ignore_case(value, start, end, b"x") {
+            (start, end) = next_subtag(value, end);
+            let mut extension = 0;
+            while start <= len && end - start >= 2 {
+                (start, end) = next_subtag(value, end);
+                extension += 1;
+            }
+            if extension == 0 {
+                return false;
+            }
+        }
+    }
+    if start <= len && equals_ignore_case(value, start, end, b"x") {
+        // Any subtag follows, but at least one.
+        return end < len;
+    }
+    start > len
+}
+
+#[derive(Clone, Copy)]
+enum Class {
+    Alpha,
+    Digit,
+    Alphanumeric,
+}
+
+const fn all(value: &[u8], mut start: usize, end: usize, class: Class) -> bool {
+    while start < end {
+        let valid = match class {
+            Class::Alpha => value[start].is_ascii_alphabetic(),
+            Class::Digit => value[start].is_ascii_digit(),
+            Class::Alphanumeric => value[start].is_ascii_alphanumeric(),
+        };
+        if !valid {
+            return false;
+        }
+        start += 1;
+    }
+    true
+}
+
+const fn subtag_end(value: &[u8], start: usize) -> usize {
+    match find(value, start, value.len(), b'-') {
+        Some(i) => i,
+        None => value.len(),
+    }
+}
+
+/// The bounds of the subtag following the one ending at `end`, starting past the end of
+/// `value` once every subtag is consumed.
+const fn next_subtag(value: &[u8], end: usize) -> (usize, usize) {
+    match end < value.len() {
+        true => (end + 1, subtag_end(value, end + 1)),
+        false => (end + 1, end + 1),
+    }
+}
+
+const fn find_unquoted(value: &[u8], mut start: usize, needle: u8) -> usize {
+    let (mut quoted, mut escaped) = (false, false);
+    while start < value.len() {
+        match value[start] {
+            _ if escaped => escaped = false,
+            b'\\' if quoted => escaped = true,
+            b'"' => quoted = !quoted,
+            c if c == needle && !quoted => return start,
+            _ => {}
+        }
+        start += 1;
+    }
+    value.len()
+}
+
+const fn find(value: &[u8], mut start: usize, end: usize, needle: u8) -> Option<usize> {
+    while start < end {
+        if value[start] == needle {
+            return Some(start);
+        }
+        start += 1;
+    }
+    None
+}
+
+const fn trim(value: &[u8], mut start: usize, mut end: usize) -> (usize, usize) {
+    while start < end && value[start].is_ascii_whitespace() {
+        start += 1;
+    }
+    while end > start && value[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    (start, end)
+}
+
+const fn equals(value: &[u8], start: usize, end: usize, expected: &[u8]) -> bool {
+    if end - start != expected.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < expected.len() {
+        if value[start + i] != expected[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn equals_ignore_case(value: &[u8], start: usize, end: usize, expected: &[u8]) -> bool {
+    end - start == expected.len() && {
+        let (_, rest) = value.split_at(start);
+        let (value, _) = rest.split_at(end - start);
+        value.eq_ignore_ascii_case(expected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_valid_supported;
+    use crate::{
+        ContentTypeNegotiation, EncodingNegotiation, LanguageNegotiation, NegotiationKind,
+        NegotiationType,
+    };
+
+    negotiator!(static LANGUAGES: LanguageNegotiation = ["en-US", "fr-FR"]);
+
+    #[test]
+    fn negotiator() {
+        assert_eq!(LANGUAGES.negotiate("fr").unwrap(), Some(&"fr-FR"));
+        assert_eq!(
+            negotiator!(
+                ContentTypeNegotiation,
+                ["text/html;level=1", "application/json",]
+            )
+            .negotiate("text/*")
+            .unwrap(),
+            Some(&"text/html;level=1")
+        );
+    }
+
+    #[test]
+    fn valid_supported() {
+        fn runtime<N: NegotiationType>(value: &str) -> bool {
+            N::parse_elem(&value).is_ok()
+        }
+
+        for value in [
+            "text/html",
+            " text/html ; level=1;charset=utf-8",
+            "text/html;",
+            "text/html;q=1",
+            "text/html; q=1",
+            "text/html;level",
+            "text/*",
+            "*/html",
+            "text",
+            "text/html/extra",
+            "text/html;level=1;q=0.5",
+            "text/html;Q=1",
+            "text/html;q =1",
+            "text/html;title=\"x;y\"",
+            "text/html;title=\"x;q=1\"",
+            "text/html;title=\"x\\\";y\";a=b",
+            "text/html;title=\"x;y",
+            "text/html; ;a=b",
+        ] {
+            assert_eq!(
+                is_valid_supported(NegotiationKind::ContentType, value),
+                runtime::<ContentTypeNegotiation>(value),
+                "{value}"
+            );
+        }
+        for value in [
+            "en-US",
+            "en",
+            "zh-Hant-TW",
+            "en-US;q=1",
+            "e",
+            "en--US",
+            "1a-US",
+            "i-klingon",
+            "I-DEFAULT",
+            "i-unknown",
+            "x-pirate",
+            "x",
+            "X-a-b",
+            "en-US-US",
+            "en-Latn-Latn",
+            "zh-yue-HK",
+            "zh-yue-cmn-wuu-hak",
+            "english-abc",
+            "sl-rozaj-biske",
+            "de-1996",
+            "de-123",
+            "en-a-bbb-x-a-ccc",
+            "en-a",
+            "en-a-x-pirate",
+            "en-a-bc-b-cd",
+            "en-x",
+            "en-US-x-",
+            "en-US-",
+            "en-toolongsubtag",
+            "en_US",
+        ] {
+            assert_eq!(
+                is_valid_supported(NegotiationKind::Language, value),
+                runtime::<LanguageNegotiation>(value),
+                "{value}"
+            );
+        }
+        for value in ["gzip", "*", "gzip;q=1"] {
+            assert_eq!(
+                is_valid_supported(NegotiationKind::Encoding, value),
+                runtime::<EncodingNegotiation>(value),
+                "{value}"
+            );
+        }
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive() {
+        use crate::{AsNegotiationStr, Negotiator};
+
+        #[derive(AsNegotiationStr, Debug, PartialEq)]
+        enum Encoding {
+            #[negotiation("br")]
+            Brotli,
+            #[negotiation("gzip")]
+            Gzip { level: u8 },
+            #[negotiation("identity")]
+            Identity(()),
+        }
+
+        assert_eq!(Encoding::Gzip { level: 6 }.as_str(), "gzip");
+        assert_eq!(Encoding::Identity(()).as_str(), "identity");
+        let negotiator = Negotiator::<EncodingNegotiation, _>::new([
+            Encoding::Brotli,
+            Encoding::Gzip { level: 6 },
+        ])
+        .unwrap();
+        assert_eq!(
+            negotiator.negotiate("gzip").unwrap(),
+            Some(&Encoding::Gzip { level: 6 })
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use std::{cmp::Ordering, collections::BTreeMap, fmt, str::FromStr};
+
+use crate::{
+    content_type::mime_precision_score,
+    params::{quote, unquote},
+    ContentTypeNegotiation, Error, HeaderElements, HeaderParams, MaybeWildcard, NegotiationType,
+    Quality,
+};
+
+/// A supported media type, with its type, subtype and parameter names lowercased and its quoted
+/// parameter values unescaped.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MediaType {
+    main: String,
+    sub: String,
+    params: BTreeMap<String, String>,
+}
+
+impl MediaType {
+    pub(crate) fn new(main: &str, sub: &str, params: HeaderParams<'_>) -> Self {
+        Self {
+            main: main.to_ascii_lowercase(),
+            sub: sub.to_ascii_lowercase(),
+            params: params
+                .iter()
+                .map(|(k, v)| (k.to_ascii_lowercase(), unquote(v).into_owned()))
+                .collect(),
+        }
+    }
+
+    pub fn main(&self) -> &str {
+        &self.main
+    }
+
+    pub fn sub(&self) -> &str {
+        &self.sub
+    }
+
+    pub fn params(&self) -> &BTreeMap<String, String> {
+        &self.params
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    pub(crate) fn without_param(&self, name: &str) -> Self {
+        let mut media_type = self.clone();
+        media_type
+            .params
+            .retain(|k, _| !k.eq_ignore_ascii_case(name));
+        media_type
+    }
+
+    /// The RFC 6839 structured syntax suffix of the subtype, e.g. `json` for
+    /// `application/problem+json`.
+    pub fn suffix(&self) -> Option<&str> {
+        self.sub.rsplit_once('+').map(|(_, suffix)| suffix)
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.main, self.sub)?;
+        for (k, v) in &self.params {
+            write!(f, ";{k}={}", quote(v))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MediaType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ContentTypeNegotiation::parse_elem(&s)
+    }
+}
+
+/// A media range of an `Accept` header, borrowing from it.
+///
+/// Ranges are ordered by the precedence used for negotiation: quality, then specificity (`*/*`,
+/// `type/*`, `type/subtype`), then parameter count, the greatest being preferred. Remaining ties
+/// are broken by comparing the ranges as text.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MediaRange<'a> {
+    main: MaybeWildcard<&'a str>,
+    sub: MaybeWildcard<&'a str>,
+    params: BTreeMap<&'a str, &'a str>,
+    extensions: BTreeMap<&'a str, &'a str>,
+    quality: Quality,
+}
+
+impl<'a> MediaRange<'a> {
+    /// The type, as sent by the client, `*` for `*/*`.
+    pub fn main(&self) -> &'a str {
+        match self.main {
+            MaybeWildcard::Specific(main) => main,
+            MaybeWildcard::Wildcard => "*",
+        }
+    }
+
+    pub fn sub(&self) -> &'a str {
+        match self.sub {
+            MaybeWildcard::Specific(sub) => sub,
+            MaybeWildcard::Wildcard => "*",
+        }
+    }
+
+    pub fn is_wildcard(&self) -> bool {
+        self.sub == MaybeWildcard::Wildcard
+    }
+
+    /// The media type parameters, preceding the quality.
+    pub fn params(&self) -> &BTreeMap<&'a str, &'a str> {
+        &self.params
+    }
+
+    /// The accept extensions, following the quality, with an empty value for bare tokens.
+    pub fn extensions(&self) -> &BTreeMap<&'a str, &'a str> {
+        &self.extensions
+    }
+
+    pub fn quality(&self) -> Quality {
+        self.quality
+    }
+}
+
+impl Ord for MediaRange<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.quality
+            .cmp(&other.quality)
+            .then_with(|| {
+                mime_precision_score(&self.main, &self.sub)
+                    .cmp(&mime_precision_score(&other.main, &other.sub))
+            })
+            .then_with(|| self.params.len().cmp(&other.params.len()))
+            .then_with(|| (self.main(), self.sub()).cmp(&(other.main(), other.sub())))
+            .then_with(|| self.params.cmp(&other.params))
+            .then_with(|| self.extensions.cmp(&other.extensions))
+    }
+}
+
+impl PartialOrd for MediaRange<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for MediaRange<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.main, self.sub)?;
+        for (k, v) in &self.params {
+            write!(f, ";{k}={v}")?;
+        }
+        if self.quality != Quality::ONE || !self.extensions.is_empty() {
+            write!(f, ";q={}", self.quality)?;
+        }
+        for (k, v) in &self.extensions {
+            match *v {
+                "" => write!(f, ";{k}")?,
+                v => write!(f, ";{k}={v}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses every media range of an `Accept` header in the order sent by the client, without
+/// negotiating.
+pub fn parse_accept(header: &str) -> Result<Vec<MediaRange<'_>>, Error> {
+    HeaderElements::<ContentTypeNegotiation>::new(header)
+        .map(|elem| {
+            let ((main, sub, params, extensions), quality) = elem?;
+            Ok(MediaRange {
+                main,
+                sub,
+                params: params.iter().collect(),
+                extensions: extensions.iter().collect(),
+                quality,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{parse_accept, MediaType};
+    use crate::{Error, Quality};
+
+    #[test]
+    fn parse() {
+        let media_type = "Application/Problem+JSON; Charset=utf-8"
+            .parse::<MediaType>()
+            .unwrap();
+        assert_eq!(media_type.main(), "application");
+        assert_eq!(media_type.sub(), "problem+json");
+        assert_eq!(media_type.suffix(), Some("json"));
+        assert_eq!(
+            media_type.params(),
+            &BTreeMap::from([("charset".to_owned(), "utf-8".to_owned())])
+        );
+        assert_eq!(media_type.param("CHARSET"), Some("utf-8"));
+        assert_eq!(
+            media_type.to_string(),
+            "application/problem+json;charset=utf-8"
+        );
+        assert_eq!(
+            media_type,
+            "application/problem+json;charset=utf-8".parse().unwrap()
+        );
+
+        assert_eq!("text/plain".parse::<MediaType>().unwrap().suffix(), None);
+        assert_eq!(
+            "text/*".parse::<MediaType>().unwrap_err(),
+            Error::InvalidWildcard
+        );
+    }
+
+    #[test]
+    fn accept() {
+        let ranges = parse_accept("text/html;level=1;q=0.5;ext, */*;q=0.1").unwrap();
+        assert_eq!(ranges.len(), 2);
+        assert_eq!((ranges[0].main(), ranges[0].sub()), ("text", "html"));
+        assert!(!ranges[0].is_wildcard());
+        assert_eq!(ranges[0].params(), &BTreeMap::from([("level", "1")]));
+        assert_eq!(ranges[0].extensions(), &BTreeMap::from([("ext", "")]));
+        assert_eq!(ranges[0].quality(), Quality::from_thousandths(500).unwrap());
+        assert_eq!(ranges[0].to_string(), "text/html;level=1;q=0.5;ext");
+        assert_eq!((ranges[1].main(), ranges[1].sub()), ("*", "*"));
+        assert!(ranges[1].is_wildcard());
+        assert_eq!(ranges[1].to_string(), "*/*;q=0.1");
+
+        assert_eq!(
+            parse_accept("text/html, */html"),
+            Err(Error::InvalidWildcard)
+        );
+    }
+
+    #[test]
+    fn ord() {
+        let mut ranges =
+            parse_accept("*/*;q=0.8, text/*, text/html;level=1, text/html, image/*;q=0.8").unwrap();
+        ranges.sort_by(|a, b| b.cmp(a));
+        assert_eq!(
+            ranges.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "text/html;level=1",
+                "text/html",
+                "text/*",
+                "image/*;q=0.8",
+                "*/*;q=0.8"
+            ]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Well-known media types, validated by the test suite, to build content type negotiators without
+//! typos nor error handling through [`Negotiator::from_static`](crate::Negotiator::from_static).
+
+use std::{fmt, ops::Deref};
+
+/// A media type known to be valid, only built through the constants of this module.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct StaticMediaType(&'static str);
+
+impl StaticMediaType {
+    pub const fn as_str(self) -> &'static str {
+        self.0
+    }
+}
+
+impl AsRef<str> for StaticMediaType {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl Deref for StaticMediaType {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl fmt::Display for StaticMediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+macro_rules! media_types {
+    ($($name:ident = $value:literal,)*) => {
+        $(pub const $name: StaticMediaType = StaticMediaType($value);)*
+
+        #[cfg(test)]
+        const ALL: &[StaticMediaType] = &[$($name),*];
+    };
+}
+
+media_types! {
+    TEXT_PLAIN = "text/plain",
+    TEXT_PLAIN_UTF_8 = "text/plain;charset=utf-8",
+    TEXT_HTML = "text/html",
+    TEXT_HTML_UTF_8 = "text/html;charset=utf-8",
+    TEXT_CSS = "text/css",
+    TEXT_CSV = "text/csv",
+    TEXT_JAVASCRIPT = "text/javascript",
+    TEXT_MARKDOWN = "text/markdown",
+    TEXT_XML = "text/xml",
+    TEXT_EVENT_STREAM = "text/event-stream",
+    APPLICATION_JSON = "application/json",
+    APPLICATION_PROBLEM_JSON = "application/problem+json",
+    APPLICATION_LD_JSON = "application/ld+json",
+    APPLICATION_NDJSON = "application/x-ndjson",
+    APPLICATION_XML = "application/xml",
+    APPLICATION_XHTML_XML = "application/xhtml+xml",
+    APPLICATION_ATOM_XML = "application/atom+xml",
+    APPLICATION_RSS_XML = "application/rss+xml",
+    APPLICATION_CBOR = "application/cbor",
+    APPLICATION_MSGPACK = "application/msgpack",
+    APPLICATION_PROTOBUF = "application/protobuf",
+    APPLICATION_YAML = "application/yaml",
+    APPLICATION_PDF = "application/pdf",
+    APPLICATION_ZIP = "application/zip",
+    APPLICATION_WASM = "application/wasm",
+    APPLICATION_OCTET_STREAM = "application/octet-stream",
+    APPLICATION_WWW_FORM_URLENCODED = "application/x-www-form-urlencoded",
+    MULTIPART_FORM_DATA = "multipart/form-data",
+    IMAGE_AVIF = "image/avif",
+    IMAGE_WEBP = "image/webp",
+    IMAGE_PNG = "image/png",
+    IMAGE_JPEG = "image/jpeg",
+    IMAGE_GIF = "image/gif",
+    IMAGE_SVG_XML = "image/svg+xml",
+    IMAGE_X_ICON = "image/x-icon",
+    AUDIO_MPEG = "audio/mpeg",
+    AUDIO_OGG = "audio/ogg",
+    VIDEO_MP4 = "video/mp4",
+    VIDEO_WEBM = "video/webm",
+    FONT_WOFF2 = "font/woff2",
+}
+
+/// Legacy names still sent by clients for the media types they alias, to pass to
+/// [`NegotiatorBuilder::aliases`](crate::NegotiatorBuilder::aliases).
+pub const LEGACY_ALIASES: &[(&str, &str)] = &[
+    ("text/json", "application/json"),
+    ("application/x-json", "application/json"),
+    ("text/xml", "application/xml"),
+    ("application/xml", "text/xml"),
+    ("text/x-markdown", "text/markdown"),
+    ("application/x-javascript", "text/javascript"),
+    ("application/javascript", "text/javascript"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{ALL, APPLICATION_JSON, IMAGE_AVIF, IMAGE_WEBP, LEGACY_ALIASES, TEXT_HTML};
+    use crate::{ContentTypeNegotiation, MediaType, Negotiator};
+
+    #[test]
+    fn valid() {
+        for media_type in ALL {
+            assert_eq!(
+                media_type.parse::<MediaType>().unwrap().to_string(),
+                media_type.as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn from_static() {
+        let negotiator = Negotiator::from_static([TEXT_HTML, APPLICATION_JSON]);
+        assert_eq!(
+            negotiator.negotiate("application/json").unwrap(),
+            Some(&APPLICATION_JSON)
+        );
+
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new([IMAGE_AVIF, IMAGE_WEBP]).unwrap();
+        assert_eq!(
+            negotiator.negotiate("image/webp,*/*;q=0.8").unwrap(),
+            Some(&IMAGE_WEBP)
+        );
+    }
+
+    #[test]
+    fn legacy_aliases() {
+        for (alias, target) in LEGACY_ALIASES {
+            assert!(alias.parse::<MediaType>().is_ok());
+            assert!(target.parse::<MediaType>().is_ok());
+        }
+        let negotiator = Negotiator::<ContentTypeNegotiation, _>::builder()
+            .extend([TEXT_HTML, APPLICATION_JSON])
+            .aliases(LEGACY_ALIASES.iter().copied())
+            .build()
+            .unwrap();
+        assert_eq!(
+            negotiator.negotiate("text/json, text/html;q=0.5").unwrap(),
+            Some(&APPLICATION_JSON)
+        );
+    }
+}
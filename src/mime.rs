@@ -0,0 +1,97 @@
+use ::mime::Mime;
+
+use crate::{
+    parse_accept, AsNegotiationStr, ContentTypeNegotiation, Error, MediaRange, Negotiator, Quality,
+};
+
+impl<T: AsNegotiationStr> Negotiator<ContentTypeNegotiation, T> {
+    /// Negotiates like [`Negotiator::negotiate`], returning the selected value as a [`Mime`].
+    ///
+    /// `Mime` values being supported as is, this is mostly useful for negotiators built from
+    /// strings.
+    pub fn negotiate_mime(&self, header: &str) -> Result<Option<Mime>, Error> {
+        self.negotiate(header)?
+            .map(|value| value.as_str().parse().map_err(|_| Error::InvalidMime))
+            .transpose()
+    }
+}
+
+impl MediaRange<'_> {
+    /// Converts the range, with its parameters but without its quality nor extensions, to a
+    /// [`Mime`].
+    pub fn to_mime(&self) -> Result<Mime, Error> {
+        self.params()
+            .iter()
+            .fold(format!("{}/{}", self.main(), self.sub()), |mime, (k, v)| {
+                format!("{mime};{k}={v}")
+            })
+            .parse()
+            .map_err(|_| Error::InvalidMime)
+    }
+}
+
+/// Parses every media range of an `Accept` header as a [`Mime`] with its quality, in the order
+/// sent by the client.
+pub fn parse_accept_mime(header: &str) -> Result<Vec<(Mime, Quality)>, Error> {
+    parse_accept(header)?
+        .iter()
+        .map(|range| Ok((range.to_mime()?, range.quality())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ::mime::{Mime, APPLICATION_JSON, STAR_STAR, TEXT_HTML, TEXT_PLAIN_UTF_8};
+
+    use super::parse_accept_mime;
+    use crate::{ContentTypeNegotiation, Negotiator, Quality};
+
+    #[test]
+    fn negotiate() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new([APPLICATION_JSON, TEXT_PLAIN_UTF_8])
+                .unwrap();
+        assert_eq!(
+            negotiator.negotiate("text/plain, */*;q=0.5").unwrap(),
+            Some(&TEXT_PLAIN_UTF_8)
+        );
+        assert_eq!(
+            negotiator.negotiate("application/*").unwrap(),
+            Some(&APPLICATION_JSON)
+        );
+    }
+
+    #[test]
+    fn negotiate_mime() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["text/html", "application/json"])
+                .unwrap();
+        assert_eq!(
+            negotiator.negotiate_mime("text/*").unwrap(),
+            Some(TEXT_HTML)
+        );
+        assert_eq!(
+            negotiator
+                .negotiate_mime("application/json;q=0.5, image/*")
+                .unwrap(),
+            Some(APPLICATION_JSON)
+        );
+        assert_eq!(
+            negotiator.negotiate_mime("image/png").unwrap(),
+            None::<Mime>
+        );
+    }
+
+    #[test]
+    fn parse() {
+        let ranges = parse_accept_mime("text/plain;charset=utf-8;q=0.5;ext, */*;q=0.1").unwrap();
+        assert_eq!(
+            ranges,
+            vec![
+                (TEXT_PLAIN_UTF_8, Quality::from_thousandths(500).unwrap()),
+                (STAR_STAR, Quality::from_thousandths(100).unwrap()),
+            ]
+        );
+        assert_eq!(ranges[0].0.get_param("charset").unwrap(), "utf-8");
+    }
+}
@@ -0,0 +1,102 @@
+use opentelemetry::{
+    trace::{get_active_span, Span},
+    KeyValue,
+};
+
+use crate::NegotiationKind;
+
+pub const CONTENT_TYPE_ATTRIBUTE: &str = "http.negotiation.content_type";
+pub const LANGUAGE_ATTRIBUTE: &str = "http.negotiation.language";
+pub const ENCODING_ATTRIBUTE: &str = "http.negotiation.encoding";
+pub const TOKEN_ATTRIBUTE: &str = "http.negotiation.token";
+
+pub fn negotiation_attribute(kind: NegotiationKind, value: &str) -> KeyValue {
+    let key = match kind {
+        NegotiationKind::ContentType => CONTENT_TYPE_ATTRIBUTE,
+        NegotiationKind::Language => LANGUAGE_ATTRIBUTE,
+        NegotiationKind::Encoding => ENCODING_ATTRIBUTE,
+        NegotiationKind::Token => TOKEN_ATTRIBUTE,
+    };
+    KeyValue::new(key, value.to_owned())
+}
+
+pub fn record_negotiation<S: Span>(span: &mut S, kind: NegotiationKind, value: &str) {
+    span.set_attribute(negotiation_attribute(kind, value));
+}
+
+pub fn record_negotiation_on_active_span(kind: NegotiationKind, value: &str) {
+    get_active_span(|span| span.set_attribute(negotiation_attribute(kind, value)));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, time::SystemTime};
+
+    use opentelemetry::{
+        trace::{Span, SpanContext, Status},
+        KeyValue,
+    };
+
+    use super::record_negotiation;
+    use crate::NegotiationKind;
+
+    struct RecordingSpan {
+        context: SpanContext,
+        attributes: Vec<KeyValue>,
+    }
+
+    impl Default for RecordingSpan {
+        fn default() -> Self {
+            Self {
+                context: SpanContext::empty_context(),
+                attributes: Vec::new(),
+            }
+        }
+    }
+
+    impl Span for RecordingSpan {
+        fn add_event_with_timestamp<T>(&mut self, _: T, _: SystemTime, _: Vec<KeyValue>)
+        where
+            T: Into<Cow<'static, str>>,
+        {
+        }
+
+        fn span_context(&self) -> &SpanContext {
+            &self.context
+        }
+
+        fn is_recording(&self) -> bool {
+            true
+        }
+
+        fn set_attribute(&mut self, attribute: KeyValue) {
+            self.attributes.push(attribute);
+        }
+
+        fn set_status(&mut self, _: Status) {}
+
+        fn update_name<T>(&mut self, _: T)
+        where
+            T: Into<Cow<'static, str>>,
+        {
+        }
+
+        fn end_with_timestamp(&mut self, _: SystemTime) {}
+    }
+
+    #[test]
+    fn record() {
+        let mut span = RecordingSpan::default();
+        record_negotiation(&mut span, NegotiationKind::ContentType, "application/json");
+        record_negotiation(&mut span, NegotiationKind::Language, "en-US");
+        record_negotiation(&mut span, NegotiationKind::Encoding, "gzip");
+        assert_eq!(
+            span.attributes,
+            vec![
+                KeyValue::new("http.negotiation.content_type", "application/json"),
+                KeyValue::new("http.negotiation.language", "en-US"),
+                KeyValue::new("http.negotiation.encoding", "gzip"),
+            ]
+        );
+    }
+}
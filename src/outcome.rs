@@ -0,0 +1,136 @@
+use std::{fmt, sync::Arc};
+
+use crate::{Error, NegotiationType, Negotiator, Quality};
+
+/// What a negotiation against a raw header ended up with, as reported to the outcome hook.
+#[derive(Copy, Clone, Debug)]
+pub struct NegotiationOutcome<'a> {
+    /// The header negotiated against, `None` when it was absent.
+    pub header: Option<&'a str>,
+    pub selected: Option<&'a str>,
+    /// The quality the client gave to the selected value, `None` on fallback.
+    pub quality: Option<Quality>,
+    /// Whether the default value was selected because nothing matched, the header was absent or
+    /// it only accepted wildcards with an explicit default.
+    pub fallback: bool,
+    pub error: Option<&'a Error>,
+}
+
+type Hook = dyn Fn(&NegotiationOutcome<'_>) + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct OutcomeHook(Arc<Hook>);
+
+impl fmt::Debug for OutcomeHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OutcomeHook")
+    }
+}
+
+impl OutcomeHook {
+    pub(crate) fn new<F>(hook: F) -> Self
+    where
+        F: Fn(&NegotiationOutcome<'_>) + Send + Sync + 'static,
+    {
+        Self(Arc::new(hook))
+    }
+}
+
+impl<N: NegotiationType, T> Negotiator<N, T> {
+    /// Calls `hook` after each negotiation against a raw header, e.g. to count the selected
+    /// variants. Negotiations against a [`ParsedHeader`](crate::ParsedHeader) are not reported.
+    pub fn on_outcome<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&NegotiationOutcome<'_>) + Send + Sync + 'static,
+    {
+        self.options.on_outcome = Some(OutcomeHook::new(hook));
+        self
+    }
+
+    pub(crate) fn report(
+        &self,
+        header: Option<&str>,
+        selected: Result<Option<&str>, &Error>,
+        quality: Option<Quality>,
+        fallback: bool,
+    ) {
+        let outcome = NegotiationOutcome {
+            header,
+            selected: selected.ok().flatten(),
+            quality,
+            fallback,
+            error: selected.err(),
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            kind = ?N::KIND,
+            header = outcome.header,
+            selected = outcome.selected,
+            quality = outcome.quality.map(Quality::as_f32),
+            fallback = outcome.fallback,
+            error = outcome.error.map(tracing::field::display),
+            "negotiated"
+        );
+        if let Some(OutcomeHook(hook)) = &self.options.on_outcome {
+            hook(&outcome);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{EncodingNegotiation, Error, Negotiator, Quality};
+
+    #[test]
+    fn on_outcome() {
+        let outcomes = Arc::new(Mutex::new(Vec::new()));
+        let negotiator = Negotiator::<EncodingNegotiation, _>::new(["gzip", "br"])
+            .unwrap()
+            .on_outcome({
+                let outcomes = Arc::clone(&outcomes);
+                move |outcome| {
+                    outcomes.lock().unwrap().push((
+                        outcome.header.map(str::to_owned),
+                        outcome.selected.map(str::to_owned),
+                        outcome.quality.map(Quality::thousandths),
+                        outcome.fallback,
+                        outcome.error.cloned(),
+                    ))
+                }
+            });
+
+        negotiator.negotiate("br;q=0.5").unwrap();
+        negotiator.negotiate_or_first(Some("zstd")).unwrap();
+        negotiator.negotiate_opt(None).unwrap();
+        negotiator.negotiate("br;q").unwrap_err();
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![
+                (
+                    Some("br;q=0.5".to_owned()),
+                    Some("br".to_owned()),
+                    Some(500),
+                    false,
+                    None
+                ),
+                (
+                    Some("zstd".to_owned()),
+                    Some("gzip".to_owned()),
+                    None,
+                    true,
+                    None
+                ),
+                (None, Some("gzip".to_owned()), None, true, None),
+                (
+                    Some("br;q".to_owned()),
+                    None,
+                    None,
+                    false,
+                    Some(Error::InvalidHeader)
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::{borrow::Cow, fmt};
+
+use crate::Error;
+
+/// The `;` separated parameters of a header element, borrowed from the header and split on
+/// iteration instead of being collected. Bare tokens, only allowed in accept extensions, have an
+/// empty value.
+#[derive(Copy, Clone, Default)]
+pub struct HeaderParams<'a> {
+    raw: &'a str,
+    // Positions of the dropped parameters, only the first 64 can be dropped.
+    dropped: u64,
+}
+
+impl<'a> HeaderParams<'a> {
+    /// Checks that every parameter has a value.
+    pub(crate) fn parse(raw: &'a str) -> Result<Self, Error> {
+        let params = Self::extensions(raw);
+        if split_unquoted(raw, ';')
+            .skip(1)
+            .any(|param| !param.contains('='))
+        {
+            return Err(Error::InvalidHeader);
+        }
+        Ok(params)
+    }
+
+    pub(crate) fn extensions(raw: &'a str) -> Self {
+        Self { raw, dropped: 0 }
+    }
+
+    /// The same parameters, skipping the ones named `name`, ignoring case.
+    pub(crate) fn without(self, name: &str) -> Self {
+        self.retain(|k| !k.eq_ignore_ascii_case(name))
+    }
+
+    /// The same parameters, skipping the ones whose name is rejected by `keep`.
+    pub(crate) fn retain(self, keep: impl Fn(&str) -> bool) -> Self {
+        let dropped = self
+            .all()
+            .take(u64::BITS as usize)
+            .enumerate()
+            .filter(|(_, (k, _))| !keep(k))
+            .fold(self.dropped, |dropped, (i, _)| dropped | 1 << i);
+        Self { dropped, ..self }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a str)> + Clone + 'a {
+        let dropped = self.dropped;
+        self.all()
+            .enumerate()
+            .filter(move |(i, _)| *i >= u64::BITS as usize || dropped & 1 << i == 0)
+            .map(|(_, param)| param)
+    }
+
+    fn all(&self) -> impl Iterator<Item = (&'a str, &'a str)> + Clone + 'a {
+        split_unquoted(self.raw, ';')
+            .map(str::trim)
+            .filter(|param| !param.is_empty())
+            .map(|param| param.split_once('=').unwrap_or((param, "")))
+    }
+
+    /// The value of the first parameter named `name`, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+// Parameters are equal regardless of their order.
+impl PartialEq for HeaderParams<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|param| other.iter().any(|o| o == param))
+    }
+}
+
+impl Eq for HeaderParams<'_> {}
+
+impl fmt::Debug for HeaderParams<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Splits on `sep` outside of quoted strings, in which a backslash escapes the next character.
+#[derive(Clone, Debug)]
+pub(crate) struct SplitUnquoted<'a> {
+    rest: Option<&'a str>,
+    sep: char,
+}
+
+pub(crate) fn split_unquoted(input: &str, sep: char) -> SplitUnquoted<'_> {
+    SplitUnquoted {
+        rest: Some(input),
+        sep,
+    }
+}
+
+impl<'a> Iterator for SplitUnquoted<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = self.rest?;
+        let (mut quoted, mut escaped) = (false, false);
+        for (i, c) in rest.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if quoted => escaped = true,
+                '"' => quoted = !quoted,
+                c if c == self.sep && !quoted => {
+                    self.rest = Some(&rest[i + c.len_utf8()..]);
+                    return Some(&rest[..i]);
+                }
+                _ => {}
+            }
+        }
+        self.rest = None;
+        Some(rest)
+    }
+}
+
+/// Strips the quotes around a quoted-string value and resolves its backslash escapes. Other
+/// values are returned as is.
+pub(crate) fn unquote(value: &str) -> Cow<'_, str> {
+    let Some(inner) = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .filter(|_| value.len() >= 2)
+    else {
+        return Cow::Borrowed(value);
+    };
+    if !inner.contains('\\') {
+        return Cow::Borrowed(inner);
+    }
+    let mut unescaped = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// Quotes and escapes a value which is not a token, as needed to serialize it back.
+pub(crate) fn quote(value: &str) -> Cow<'_, str> {
+    if !value.is_empty() && value.chars().all(is_token_char) {
+        return Cow::Borrowed(value);
+    }
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    Cow::Owned(quoted)
+}
+
+pub(crate) fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{quote, split_unquoted, unquote, HeaderParams};
+    use crate::Error;
+
+    #[test]
+    fn params() {
+        let params = HeaderParams::parse(";level=1; charset=utf-8").unwrap();
+        assert_eq!(
+            params.iter().collect::<Vec<_>>(),
+            vec![("level", "1"), ("charset", "utf-8")]
+        );
+        assert_eq!(params.get("Charset"), Some("utf-8"));
+        assert_eq!(params.len(), 2);
+        assert_eq!(
+            params,
+            HeaderParams::parse("; charset=utf-8;level=1").unwrap()
+        );
+        assert_eq!(
+            params.without("level"),
+            HeaderParams::parse(";charset=utf-8").unwrap()
+        );
+        assert_eq!(
+            params.retain(|k| k == "level").iter().collect::<Vec<_>>(),
+            vec![("level", "1")]
+        );
+        assert!(params.retain(|_| false).is_empty());
+        assert!(HeaderParams::parse("").unwrap().is_empty());
+        assert_eq!(HeaderParams::parse(";level"), Err(Error::InvalidHeader));
+
+        assert_eq!(
+            HeaderParams::extensions("ext=1;flag")
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![("ext", "1"), ("flag", "")]
+        );
+
+        let params = HeaderParams::parse(r#";title="a;b\";c";level=1"#).unwrap();
+        assert_eq!(params.get("title"), Some(r#""a;b\";c""#));
+        assert_eq!(params.get("level"), Some("1"));
+    }
+
+    #[test]
+    fn quoted_strings() {
+        assert_eq!(
+            split_unquoted(r#"a;title="x;\"y,z";b"#, ';').collect::<Vec<_>>(),
+            vec!["a", r#"title="x;\"y,z""#, "b"]
+        );
+        assert_eq!(
+            split_unquoted(r#"text/html;t="a,b", */*"#, ',').collect::<Vec<_>>(),
+            vec![r#"text/html;t="a,b""#, " */*"]
+        );
+        assert_eq!(unquote(r#""he said \"hi\"""#), r#"he said "hi""#);
+        assert_eq!(unquote(r#""a\\b""#), r"a\b");
+        assert_eq!(unquote(r#""plain""#), "plain");
+        assert_eq!(unquote("token"), "token");
+        assert_eq!(unquote("\""), "\"");
+
+        assert_eq!(quote("utf-8"), "utf-8");
+        assert_eq!(quote(r#"he said "hi""#), r#""he said \"hi\"""#);
+        assert_eq!(quote("a b"), r#""a b""#);
+        assert_eq!(quote(""), r#""""#);
+    }
+}
@@ -0,0 +1,515 @@
+//! Propagation of a negotiation outcome to services sitting behind a gateway.
+//!
+//! The outcome is carried by the `X-Negotiated-Variant` header as a comma separated list of
+//! `header-name="value"` pairs, where the name is the lowercase negotiation header that was used
+//! (`accept`, `accept-language`, ...) and the value the selected variant, quoted with `"` and `\`
+//! backslash-escaped:
+//!
+//! ```text
+//! X-Negotiated-Variant: accept="application/json", accept-language="en-US"
+//! ```
+//!
+//! A signed header ends with a `sig` pair holding the hex encoded HMAC-SHA256 of the canonical
+//! unsigned form (pairs joined by `", "`), so downstream services sharing the key can detect
+//! spoofed values:
+//!
+//! ```text
+//! X-Negotiated-Variant: accept="application/json", sig="9f86d0..."
+//! ```
+//!
+//! A header naming the same negotiation header twice is rejected, the gateway never emits one.
+//!
+//! With the `axum` feature, [`PropagationLayer`] negotiates at the gateway and sets the header on
+//! the forwarded request, replacing any value sent by the client.
+
+#[cfg(feature = "axum")]
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use hmac::{Hmac, Mac};
+#[cfg(feature = "axum")]
+use http::{HeaderValue, Request};
+use sha2::Sha256;
+#[cfg(feature = "axum")]
+use tower_layer::Layer;
+#[cfg(feature = "axum")]
+use tower_service::Service;
+
+use crate::Error;
+#[cfg(feature = "axum")]
+use crate::{combined_header, AsNegotiationStr, NegotiationType, Negotiator};
+
+pub const NEGOTIATED_VARIANT_HEADER: &str = "x-negotiated-variant";
+
+const SIGNATURE_KEY: &str = "sig";
+
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct NegotiatedVariant {
+    entries: Vec<(String, String)>,
+}
+
+impl NegotiatedVariant {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, header: &str, value: &str) -> Self {
+        self.insert(header, value);
+        self
+    }
+
+    pub fn insert(&mut self, header: &str, value: &str) {
+        let header = header.to_ascii_lowercase();
+        match self.entries.iter_mut().find(|(h, _)| *h == header) {
+            Some((_, v)) => *v = value.to_owned(),
+            None => self.entries.push((header, value.to_owned())),
+        }
+    }
+
+    pub fn get(&self, header: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(h, _)| h.eq_ignore_ascii_case(header))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(h, v)| (h.as_str(), v.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn encode(&self) -> String {
+        self.entries
+            .iter()
+            .map(|(h, v)| format!("{h}={}", quote(v)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn encode_signed(&self, key: &[u8]) -> String {
+        let unsigned = self.encode();
+        let signature = to_hex(&sign(key, &unsigned));
+        if unsigned.is_empty() {
+            format!("{SIGNATURE_KEY}=\"{signature}\"")
+        } else {
+            format!("{unsigned}, {SIGNATURE_KEY}=\"{signature}\"")
+        }
+    }
+
+    /// Decodes the header without verifying it. A `sig` pair, if any, is discarded.
+    pub fn decode(input: &str) -> Result<Self, Error> {
+        let (variant, _) = Self::decode_parts(input)?;
+        match variant.has_duplicates() {
+            true => Err(Error::InvalidHeader),
+            false => Ok(variant),
+        }
+    }
+
+    pub fn decode_signed(input: &str, key: &[u8]) -> Result<Self, Error> {
+        let (variant, signature) = Self::decode_parts(input)?;
+        let signature =
+            from_hex(&signature.ok_or(Error::MissingSignature)?).ok_or(Error::InvalidSignature)?;
+        // The signed form never holds a header twice, a duplicate is treated as tampering.
+        if variant.has_duplicates() {
+            return Err(Error::InvalidSignature);
+        }
+        mac(key)
+            .chain_update(variant.encode())
+            .verify_slice(&signature)
+            .map_err(|_| Error::InvalidSignature)?;
+        Ok(variant)
+    }
+
+    fn has_duplicates(&self) -> bool {
+        self.entries
+            .iter()
+            .enumerate()
+            .any(|(i, (header, _))| self.entries[..i].iter().any(|(h, _)| h == header))
+    }
+
+    /// Decodes the pairs as they are, duplicates included.
+    fn decode_parts(input: &str) -> Result<(Self, Option<String>), Error> {
+        let mut variant = Self::new();
+        let mut signature = None;
+        let mut rest = input.trim();
+        while !rest.is_empty() {
+            let (header, after) = rest.split_once('=').ok_or(Error::MissingSeparator('='))?;
+            let header = header.trim();
+            if header.is_empty() || signature.is_some() {
+                return Err(Error::InvalidHeader);
+            }
+            let (value, after) = unquote(after.trim_start())?;
+            if header.eq_ignore_ascii_case(SIGNATURE_KEY) {
+                signature = Some(value);
+            } else {
+                variant.entries.push((header.to_ascii_lowercase(), value));
+            }
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after.trim_start();
+                if rest.is_empty() {
+                    return Err(Error::InvalidHeader);
+                }
+            } else if !rest.is_empty() {
+                return Err(Error::InvalidHeader);
+            }
+        }
+        Ok((variant, signature))
+    }
+}
+
+/// Negotiates with the wrapped negotiator and sets the outcome on the `X-Negotiated-Variant`
+/// header of the request passed to the inner service. Stacked layers add their own pair to the
+/// same header.
+#[cfg(feature = "axum")]
+pub struct PropagationLayer<N: NegotiationType, T> {
+    negotiator: Arc<Negotiator<N, T>>,
+    key: Option<Arc<[u8]>>,
+}
+
+#[cfg(feature = "axum")]
+impl<N: NegotiationType, T> PropagationLayer<N, T> {
+    pub fn new(negotiator: Negotiator<N, T>) -> Self {
+        Self {
+            negotiator: Arc::new(negotiator),
+            key: None,
+        }
+    }
+
+    /// Signs the emitted header with `key`.
+    pub fn signed(mut self, key: &[u8]) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<N: NegotiationType, T> Clone for PropagationLayer<N, T> {
+    fn clone(&self) -> Self {
+        Self {
+            negotiator: Arc::clone(&self.negotiator),
+            key: self.key.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<S, N: NegotiationType, T> Layer<S> for PropagationLayer<N, T> {
+    type Service = PropagationService<S, N, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PropagationService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "axum")]
+pub struct PropagationService<S, N: NegotiationType, T> {
+    inner: S,
+    layer: PropagationLayer<N, T>,
+}
+
+#[cfg(feature = "axum")]
+impl<S: Clone, N: NegotiationType, T> Clone for PropagationService<S, N, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<S, B, N, T> Service<Request<B>> for PropagationService<S, N, T>
+where
+    S: Service<Request<B>>,
+    N: NegotiationType,
+    T: AsNegotiationStr,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        let negotiator = &self.layer.negotiator;
+        let name = negotiator.header_name();
+        let selected = negotiator
+            .negotiate_or_first(combined_header(req.headers(), &name).as_deref())
+            .ok()
+            .map(|selected| selected.as_str().to_owned());
+
+        // Pairs added by outer layers are kept in the extensions, the header received from the
+        // client is never trusted.
+        let mut variant = req
+            .extensions_mut()
+            .remove::<NegotiatedVariant>()
+            .unwrap_or_default();
+        if let Some(selected) = selected {
+            variant.insert(name.as_str(), &selected);
+        }
+        let encoded = match &self.layer.key {
+            Some(key) => variant.encode_signed(key),
+            None => variant.encode(),
+        };
+        match HeaderValue::from_str(&encoded) {
+            Ok(value) if !variant.is_empty() => {
+                req.headers_mut().insert(NEGOTIATED_VARIANT_HEADER, value);
+            }
+            _ => {
+                req.headers_mut().remove(NEGOTIATED_VARIANT_HEADER);
+            }
+        }
+        req.extensions_mut().insert(variant);
+        self.inner.call(req)
+    }
+}
+
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn unquote(input: &str) -> Result<(String, &str), Error> {
+    let mut chars = input
+        .strip_prefix('"')
+        .ok_or(Error::InvalidHeader)?
+        .char_indices();
+    let mut value = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &input[i + 2..])),
+            '\\' => value.push(chars.next().ok_or(Error::InvalidHeader)?.1),
+            c => value.push(c),
+        }
+    }
+    Err(Error::InvalidHeader)
+}
+
+fn mac(key: &[u8]) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
+
+fn sign(key: &[u8], data: &str) -> Vec<u8> {
+    mac(key).chain_update(data).finalize().into_bytes().to_vec()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NegotiatedVariant;
+    use crate::Error;
+
+    #[test]
+    fn encode() {
+        assert_eq!(NegotiatedVariant::new().encode(), "");
+
+        assert_eq!(
+            NegotiatedVariant::new()
+                .with("Accept", "application/json")
+                .with("accept-language", "en-US")
+                .encode(),
+            r#"accept="application/json", accept-language="en-US""#
+        );
+
+        assert_eq!(
+            NegotiatedVariant::new()
+                .with("accept", "text/html")
+                .with("accept", "text/plain;format=\"flowed\"")
+                .encode(),
+            r#"accept="text/plain;format=\"flowed\"""#
+        );
+    }
+
+    #[test]
+    fn decode() {
+        let variant = NegotiatedVariant::decode(
+            r#"accept="text/plain;format=\"flowed\"", Accept-Language = "en-US""#,
+        )
+        .unwrap();
+        assert_eq!(variant.get("accept"), Some("text/plain;format=\"flowed\""));
+        assert_eq!(variant.get("accept-language"), Some("en-US"));
+        assert_eq!(variant.get("accept-encoding"), None);
+
+        assert_eq!(
+            NegotiatedVariant::decode("").unwrap(),
+            NegotiatedVariant::new()
+        );
+
+        assert_eq!(
+            NegotiatedVariant::decode("accept").unwrap_err(),
+            Error::MissingSeparator('=')
+        );
+
+        assert_eq!(
+            NegotiatedVariant::decode("accept=text/html").unwrap_err(),
+            Error::InvalidHeader
+        );
+
+        assert_eq!(
+            NegotiatedVariant::decode(r#"accept="text/html","#).unwrap_err(),
+            Error::InvalidHeader
+        );
+
+        assert_eq!(
+            NegotiatedVariant::decode(r#"accept="text/html"#).unwrap_err(),
+            Error::InvalidHeader
+        );
+
+        assert_eq!(
+            NegotiatedVariant::decode(r#"accept="text/html", Accept="*/*""#).unwrap_err(),
+            Error::InvalidHeader
+        );
+    }
+
+    #[test]
+    fn signed() {
+        let variant = NegotiatedVariant::new()
+            .with("accept", "application/json")
+            .with("accept-language", "en-US");
+        let encoded = variant.encode_signed(b"secret");
+
+        assert_eq!(
+            NegotiatedVariant::decode_signed(&encoded, b"secret").unwrap(),
+            variant
+        );
+        assert_eq!(NegotiatedVariant::decode(&encoded).unwrap(), variant);
+
+        assert_eq!(
+            NegotiatedVariant::decode_signed(&encoded, b"other").unwrap_err(),
+            Error::InvalidSignature
+        );
+
+        assert_eq!(
+            NegotiatedVariant::decode_signed(
+                &encoded.replace("application/json", "text/html"),
+                b"secret"
+            )
+            .unwrap_err(),
+            Error::InvalidSignature
+        );
+
+        assert_eq!(
+            NegotiatedVariant::decode_signed(&variant.encode(), b"secret").unwrap_err(),
+            Error::MissingSignature
+        );
+
+        assert_eq!(
+            NegotiatedVariant::decode_signed(&format!("{encoded}, accept=\"*/*\""), b"secret")
+                .unwrap_err(),
+            Error::InvalidHeader
+        );
+
+        let spoofed = NegotiatedVariant::new().with("accept", "application/json");
+        assert_eq!(
+            NegotiatedVariant::decode_signed(
+                &format!("accept=\"text/html\", {}", spoofed.encode_signed(b"secret")),
+                b"secret"
+            )
+            .unwrap_err(),
+            Error::InvalidSignature
+        );
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn layer() {
+        use std::convert::Infallible;
+
+        use http::{
+            header::{ACCEPT, ACCEPT_LANGUAGE},
+            Request,
+        };
+        use tower::{service_fn, Layer, ServiceExt};
+
+        use super::{PropagationLayer, NEGOTIATED_VARIANT_HEADER};
+        use crate::{ContentTypeNegotiation, LanguageNegotiation, Negotiator};
+
+        let echo = service_fn(|req: Request<()>| async move {
+            Ok::<_, Infallible>(
+                req.headers()
+                    .get(NEGOTIATED_VARIANT_HEADER)
+                    .map(|value| value.to_str().unwrap().to_owned()),
+            )
+        });
+        let content = PropagationLayer::new(
+            Negotiator::<ContentTypeNegotiation, _>::new(["application/json", "text/html"])
+                .unwrap(),
+        )
+        .signed(b"secret");
+        let language =
+            PropagationLayer::new(Negotiator::<LanguageNegotiation, _>::new(["en-US"]).unwrap())
+                .signed(b"secret");
+        let service = content.layer(language.layer(echo));
+
+        let header = service
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .header(ACCEPT, "text/*")
+                    .header(ACCEPT_LANGUAGE, "en")
+                    .header(NEGOTIATED_VARIANT_HEADER, r#"accept="image/png""#)
+                    .body(())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            NegotiatedVariant::decode_signed(&header, b"secret").unwrap(),
+            NegotiatedVariant::new()
+                .with("accept", "text/html")
+                .with("accept-language", "en-US")
+        );
+
+        let header = PropagationLayer::new(
+            Negotiator::<ContentTypeNegotiation, _>::builder()
+                .supported("text/html")
+                .strict(true)
+                .build()
+                .unwrap(),
+        )
+        .layer(echo)
+        .oneshot(
+            Request::builder()
+                .header(ACCEPT, "image/png")
+                .header(NEGOTIATED_VARIANT_HEADER, r#"accept="image/png""#)
+                .body(())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(header, None);
+    }
+}
@@ -0,0 +1,143 @@
+//! Bindings for running the negotiation inside proxy-wasm filters (e.g. Envoy), so the edge picks
+//! variants with exactly the same algorithm as the origin.
+//!
+//! Inputs are raw byte slices: the supported variants separated by `\n` and the request header
+//! value. The result is the index of the selected variant, [`NO_MATCH`] when none is acceptable or
+//! [`INVALID_INPUT`] when either input cannot be parsed. None of these functions panic.
+
+use std::{panic, slice, str};
+
+use crate::{
+    token::AnyTokenNegotiation, ContentTypeNegotiation, EncodingNegotiation, LanguageNegotiation,
+    NegotiationKind, NegotiationType, Negotiator,
+};
+
+pub const NO_MATCH: i32 = -1;
+pub const INVALID_INPUT: i32 = -2;
+
+pub fn pick_variant_index(kind: NegotiationKind, supported: &[u8], header: &[u8]) -> i32 {
+    let (Ok(supported), Ok(header)) = (str::from_utf8(supported), str::from_utf8(header)) else {
+        return INVALID_INPUT;
+    };
+    let variants = supported.split('\n').map(str::trim).collect::<Vec<_>>();
+    match kind {
+        NegotiationKind::ContentType => pick::<ContentTypeNegotiation>(&variants, header),
+        NegotiationKind::Language => pick::<LanguageNegotiation>(&variants, header),
+        NegotiationKind::Encoding => pick::<EncodingNegotiation>(&variants, header),
+        NegotiationKind::Token => pick::<AnyTokenNegotiation>(&variants, header),
+    }
+}
+
+fn pick<N: NegotiationType>(variants: &[&str], header: &str) -> i32 {
+    let Ok(negotiator) = Negotiator::<N, _>::new(
+        variants
+            .iter()
+            .copied()
+            .enumerate()
+            .map(|(i, v)| Indexed(i, v)),
+    ) else {
+        return INVALID_INPUT;
+    };
+    match negotiator.negotiate(header) {
+        Ok(Some(Indexed(i, _))) => i32::try_from(*i).unwrap_or(INVALID_INPUT),
+        Ok(None) => NO_MATCH,
+        Err(_) => INVALID_INPUT,
+    }
+}
+
+struct Indexed<'a>(usize, &'a str);
+
+impl AsRef<str> for Indexed<'_> {
+    fn as_ref(&self) -> &str {
+        self.1
+    }
+}
+
+/// Exported entry point. `kind` is 0 for `Accept`, 1 for `Accept-Language`, 2 for
+/// `Accept-Encoding` and 3 for any other token list header.
+///
+/// # Safety
+///
+/// Each pointer must either be null with a zero length or point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn http_negotiator_pick_variant(
+    kind: u32,
+    supported_ptr: *const u8,
+    supported_len: usize,
+    header_ptr: *const u8,
+    header_len: usize,
+) -> i32 {
+    let kind = match kind {
+        0 => NegotiationKind::ContentType,
+        1 => NegotiationKind::Language,
+        2 => NegotiationKind::Encoding,
+        3 => NegotiationKind::Token,
+        _ => return INVALID_INPUT,
+    };
+    let (Some(supported), Some(header)) = (
+        bytes(supported_ptr, supported_len),
+        bytes(header_ptr, header_len),
+    ) else {
+        return INVALID_INPUT;
+    };
+    panic::catch_unwind(|| pick_variant_index(kind, supported, header)).unwrap_or(INVALID_INPUT)
+}
+
+unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (ptr.is_null(), len) {
+        (true, 0) => Some(&[]),
+        (true, _) => None,
+        (false, len) => Some(slice::from_raw_parts(ptr, len)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{http_negotiator_pick_variant, pick_variant_index, INVALID_INPUT, NO_MATCH};
+    use crate::NegotiationKind;
+
+    #[test]
+    fn pick() {
+        assert_eq!(
+            pick_variant_index(
+                NegotiationKind::ContentType,
+                b"text/html\napplication/json",
+                b"application/json, text/html;q=0.5"
+            ),
+            1
+        );
+        assert_eq!(
+            pick_variant_index(NegotiationKind::Language, b"en-US\nfr-FR", b"de"),
+            NO_MATCH
+        );
+        assert_eq!(
+            pick_variant_index(NegotiationKind::Encoding, b"gzip\n*", b"gzip"),
+            INVALID_INPUT
+        );
+        assert_eq!(
+            pick_variant_index(NegotiationKind::Encoding, b"gzip", b"gz\xffip"),
+            INVALID_INPUT
+        );
+    }
+
+    #[test]
+    fn exported() {
+        let supported = b"gzip\nbr";
+        let header = b"br, gzip;q=0.5";
+        let pick = |kind| unsafe {
+            http_negotiator_pick_variant(
+                kind,
+                supported.as_ptr(),
+                supported.len(),
+                header.as_ptr(),
+                header.len(),
+            )
+        };
+        assert_eq!(pick(2), 1);
+        assert_eq!(pick(7), INVALID_INPUT);
+        assert_eq!(
+            unsafe { http_negotiator_pick_variant(2, std::ptr::null(), 4, header.as_ptr(), 2) },
+            INVALID_INPUT
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use std::{fmt, str::FromStr};
+
+use thiserror::Error as ThisError;
+
+use crate::Error;
+
+/// A quality or weight between 0 and 1, stored as thousandths as allowed by the RFC 9110
+/// `qvalue` grammar.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Quality(u16);
+
+impl Quality {
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(1000);
+
+    pub const fn from_thousandths(thousandths: u16) -> Option<Self> {
+        if thousandths <= 1000 {
+            Some(Self(thousandths))
+        } else {
+            None
+        }
+    }
+
+    pub const fn thousandths(self) -> u16 {
+        self.0
+    }
+
+    pub(crate) const fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+
+    pub fn as_f32(self) -> f32 {
+        f32::from(self.0) / 1000.
+    }
+
+    /// Splits an element only allowed a `q` parameter into its value and quality. Browser quirks
+    /// allow spaces around `=`.
+    pub(crate) fn split_weight(input: &str, quirks: bool) -> Result<(&str, Self), Error> {
+        let mut parts = input.split(';').map(str::trim);
+        let value = parts.next().ok_or(Error::InvalidHeader)?;
+        let q = match parts.next() {
+            Some(first_param) => {
+                let (k, v) = first_param.split_once('=').ok_or(Error::InvalidHeader)?;
+                let (k, v) = match quirks {
+                    true => (k.trim_end(), v.trim_start()),
+                    false => (k, v),
+                };
+                if !k.eq_ignore_ascii_case("q") || parts.next().is_some() {
+                    return Err(Error::ParamsNotAllowed);
+                }
+                Self::parse_lenient(v)?
+            }
+            None => Self::ONE,
+        };
+        Ok((value, q))
+    }
+
+    /// Parses any float, as sent by clients not following the grammar, clamping it between 0 and
+    /// 1 and rounding it to thousandths.
+    pub(crate) fn parse_lenient(input: &str) -> Result<Self, Error> {
+        let q = input.parse::<f32>().map_err(|_| Error::InvalidQuality {
+            source: ParseQualityError::Malformed,
+        })?;
+        if q.is_nan() {
+            return Ok(Self::ZERO);
+        }
+        Ok(Self((q.clamp(0., 1.) * 1000.).round() as u16))
+    }
+}
+
+impl FromStr for Quality {
+    type Err = Error;
+
+    /// Parses a quality following the RFC 9110 grammar: `0` or `1`, optionally followed by up to
+    /// three decimals, which must be zeros after `1`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = |source| Err(Error::InvalidQuality { source });
+        let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        let (int, dec) = input.split_once('.').unwrap_or((input, ""));
+        if int.is_empty() || !digits(int) || !digits(dec) {
+            return invalid(ParseQualityError::Malformed);
+        }
+        if dec.len() > 3 {
+            return invalid(ParseQualityError::TooManyDecimals);
+        }
+        let thousandths = dec
+            .bytes()
+            .chain(std::iter::repeat(b'0'))
+            .take(3)
+            .fold(0, |acc, b| acc * 10 + u16::from(b - b'0'));
+        match (int, thousandths) {
+            ("0", _) => Ok(Self(thousandths)),
+            ("1", 0) => Ok(Self::ONE),
+            _ => invalid(ParseQualityError::OutOfRange),
+        }
+    }
+}
+
+/// Why a quality does not follow the RFC 9110 `qvalue` grammar.
+#[derive(ThisError, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ParseQualityError {
+    #[error("quality is not a decimal number")]
+    Malformed,
+    #[error("quality has more than three decimals")]
+    TooManyDecimals,
+    #[error("quality must be between 0 and 1")]
+    OutOfRange,
+}
+
+impl TryFrom<f32> for Quality {
+    type Error = Error;
+
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        if !(0. ..=1.).contains(&value) {
+            return Err(Error::InvalidWeight);
+        }
+        Ok(Self((value * 1000.).round() as u16))
+    }
+}
+
+impl From<Quality> for f32 {
+    fn from(quality: Quality) -> Self {
+        quality.as_f32()
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            1000 => f.write_str("1"),
+            0 => f.write_str("0"),
+            q => {
+                let dec = format!("{q:03}");
+                write!(f, "0.{}", dec.trim_end_matches('0'))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseQualityError, Quality};
+    use crate::Error;
+
+    #[test]
+    fn parse() {
+        assert_eq!("1".parse(), Ok(Quality::ONE));
+        assert_eq!("1.000".parse(), Ok(Quality::ONE));
+        assert_eq!("0".parse(), Ok(Quality::ZERO));
+        assert_eq!("0.5".parse(), Ok(Quality(500)));
+        assert_eq!("0.125".parse(), Ok(Quality(125)));
+        assert_eq!("0.".parse(), Ok(Quality::ZERO));
+        for (invalid, source) in [
+            ("", ParseQualityError::Malformed),
+            ("-1", ParseQualityError::Malformed),
+            (".5", ParseQualityError::Malformed),
+            ("0.5x", ParseQualityError::Malformed),
+            ("abc", ParseQualityError::Malformed),
+            ("0.1234", ParseQualityError::TooManyDecimals),
+            ("1.001", ParseQualityError::OutOfRange),
+            ("2", ParseQualityError::OutOfRange),
+        ] {
+            assert_eq!(
+                invalid.parse::<Quality>(),
+                Err(Error::InvalidQuality { source }),
+                "{invalid}"
+            );
+        }
+
+        assert_eq!(Quality::parse_lenient("5"), Ok(Quality::ONE));
+        assert_eq!(Quality::parse_lenient("-1"), Ok(Quality::ZERO));
+        assert_eq!(Quality::parse_lenient("0.1234567"), Ok(Quality(123)));
+        assert_eq!(
+            Quality::parse_lenient("x"),
+            Err(Error::InvalidQuality {
+                source: ParseQualityError::Malformed
+            })
+        );
+    }
+
+    #[test]
+    fn convert() {
+        assert_eq!(Quality::try_from(0.25), Ok(Quality(250)));
+        assert_eq!(Quality::try_from(1.5), Err(Error::InvalidWeight));
+        assert_eq!(Quality::from_thousandths(1001), None);
+        assert_eq!(Quality(250).as_f32(), 0.25);
+        assert!(Quality(250) < Quality(300));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Quality::ONE.to_string(), "1");
+        assert_eq!(Quality::ZERO.to_string(), "0");
+        assert_eq!(Quality(500).to_string(), "0.5");
+        assert_eq!(Quality(5).to_string(), "0.005");
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use crate::{Error, NegotiationKind, NegotiationType, Negotiator};
+
+/// Object-safe view of a negotiator, selecting values by their index in the supported list.
+pub trait DynNegotiator: Send + Sync {
+    fn kind(&self) -> NegotiationKind;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn supported_str(&self, index: usize) -> Option<&str>;
+
+    /// Selects like [`Negotiator::negotiate`], an explicit default missing from the supported
+    /// list having no index.
+    fn negotiate_index(&self, header: &str) -> Result<Option<usize>, Error>;
+}
+
+impl<N, T> DynNegotiator for Negotiator<N, T>
+where
+    N: NegotiationType + Send + Sync,
+    N::Parsed: Send + Sync,
+    T: Send + Sync,
+{
+    fn kind(&self) -> NegotiationKind {
+        N::KIND
+    }
+
+    fn len(&self) -> usize {
+        Negotiator::len(self)
+    }
+
+    fn supported_str(&self, index: usize) -> Option<&str> {
+        self.supported.get(index).map(|entry| entry.source.as_str())
+    }
+
+    fn negotiate_index(&self, header: &str) -> Result<Option<usize>, Error> {
+        let ranges = self.parse_header(header)?;
+        Ok(self.select(&ranges)?.and_then(|selected| selected.index))
+    }
+}
+
+/// Negotiators of any type, keyed by the case-insensitive name of the header they negotiate.
+#[derive(Default)]
+pub struct NegotiatorRegistry {
+    negotiators: HashMap<String, Box<dyn DynNegotiator>>,
+}
+
+impl NegotiatorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<D>(&mut self, header: &str, negotiator: D) -> Option<Box<dyn DynNegotiator>>
+    where
+        D: DynNegotiator + 'static,
+    {
+        self.negotiators
+            .insert(header.to_ascii_lowercase(), Box::new(negotiator))
+    }
+
+    pub fn with<D>(mut self, header: &str, negotiator: D) -> Self
+    where
+        D: DynNegotiator + 'static,
+    {
+        self.insert(header, negotiator);
+        self
+    }
+
+    pub fn get(&self, header: &str) -> Option<&dyn DynNegotiator> {
+        self.negotiators
+            .get(&header.to_ascii_lowercase())
+            .map(Box::as_ref)
+    }
+
+    pub fn remove(&mut self, header: &str) -> Option<Box<dyn DynNegotiator>> {
+        self.negotiators.remove(&header.to_ascii_lowercase())
+    }
+
+    pub fn headers(&self) -> impl Iterator<Item = &str> {
+        self.negotiators.keys().map(String::as_str)
+    }
+
+    /// Negotiates `value` with the negotiator registered for `header`, returning the selected
+    /// supported string. `None` means no negotiator is registered for that header.
+    pub fn negotiate(&self, header: &str, value: &str) -> Option<Result<Option<&str>, Error>> {
+        let negotiator = self.get(header)?;
+        Some(
+            negotiator
+                .negotiate_index(value)
+                .map(|i| i.and_then(|i| negotiator.supported_str(i))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NegotiatorRegistry;
+    use crate::{
+        ContentTypeNegotiation, EncodingNegotiation, Error, LanguageNegotiation, NegotiationKind,
+        Negotiator,
+    };
+
+    #[test]
+    fn registry() {
+        let registry = NegotiatorRegistry::new()
+            .with(
+                "Accept",
+                Negotiator::<ContentTypeNegotiation, _>::new(["application/json", "text/html"])
+                    .unwrap(),
+            )
+            .with(
+                "accept-language",
+                Negotiator::<LanguageNegotiation, _>::new(["en-US".to_owned()]).unwrap(),
+            )
+            .with(
+                "x-compression",
+                Negotiator::<EncodingNegotiation, _>::new(["br", "gzip"]).unwrap(),
+            );
+
+        let accept = registry.get("ACCEPT").unwrap();
+        assert_eq!(accept.kind(), NegotiationKind::ContentType);
+        assert_eq!(accept.negotiate_index("text/*").unwrap(), Some(1));
+        assert_eq!(accept.supported_str(1), Some("text/html"));
+
+        assert_eq!(
+            registry.negotiate("x-compression", "gzip"),
+            Some(Ok(Some("gzip")))
+        );
+        assert_eq!(registry.negotiate("accept-language", "fr"), Some(Ok(None)));
+        assert_eq!(
+            registry
+                .negotiate("accept-language", "en;q=x")
+                .map(|r| r.is_err()),
+            Some(true)
+        );
+        assert_eq!(registry.negotiate("te", "trailers"), None);
+        assert!(matches!(
+            registry.negotiate("accept", "text"),
+            Some(Err(Error::MissingSeparator('/')))
+        ));
+    }
+}
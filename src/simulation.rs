@@ -0,0 +1,91 @@
+use crate::{Error, NegotiationType, Negotiator};
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct SimulationReport {
+    pub variants: Vec<(String, f64)>,
+    pub not_acceptable: f64,
+    pub invalid: f64,
+}
+
+impl SimulationReport {
+    pub fn share(&self, variant: &str) -> Option<f64> {
+        self.variants
+            .iter()
+            .find(|(v, _)| v == variant)
+            .map(|(_, share)| *share)
+    }
+}
+
+impl<N: NegotiationType, T> Negotiator<N, T> {
+    /// Negotiates every header of a weighted traffic sample and reports the expected share of
+    /// requests served by each supported variant, in supported order, followed by the explicit
+    /// default when it is not supported.
+    pub fn simulate<'a, I>(&self, traffic: I) -> SimulationReport
+    where
+        I: IntoIterator<Item = (&'a str, f64)>,
+    {
+        let mut report = SimulationReport {
+            variants: self
+                .supported
+                .iter()
+                .map(|entry| (entry.source.clone(), 0.))
+                .chain(
+                    self.default
+                        .as_deref()
+                        .filter(|d| self.supported.iter().all(|e| e.source != d.source))
+                        .map(|default| (default.source.clone(), 0.)),
+                )
+                .collect(),
+            ..SimulationReport::default()
+        };
+        let mut total = 0.;
+        for (header, weight) in traffic {
+            total += weight;
+            match self
+                .parse_header(header)
+                .and_then(|ranges| self.select(&ranges))
+            {
+                Ok(Some(selected)) => {
+                    report.variants[selected.index.unwrap_or(self.len())].1 += weight
+                }
+                Ok(None) | Err(Error::NotAcceptable) => report.not_acceptable += weight,
+                Err(_) => report.invalid += weight,
+            }
+        }
+        if total > 0. {
+            report
+                .variants
+                .iter_mut()
+                .for_each(|(_, share)| *share /= total);
+            report.not_acceptable /= total;
+            report.invalid /= total;
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ContentTypeNegotiation, Negotiator};
+
+    #[test]
+    fn simulate() {
+        let negotiator =
+            Negotiator::<ContentTypeNegotiation, _>::new(["image/avif", "image/webp", "image/png"])
+                .unwrap();
+        let report = negotiator.simulate([
+            ("image/avif,image/webp,*/*;q=0.8", 6.),
+            ("image/webp,*/*;q=0.8", 2.),
+            ("image/jpeg", 1.),
+            ("image", 1.),
+        ]);
+        assert_eq!(report.share("image/avif"), Some(0.6));
+        assert_eq!(report.share("image/webp"), Some(0.2));
+        assert_eq!(report.share("image/png"), Some(0.));
+        assert_eq!(report.share("image/jpeg"), None);
+        assert_eq!(report.not_acceptable, 0.1);
+        assert_eq!(report.invalid, 0.1);
+
+        assert_eq!(negotiator.simulate([]).share("image/avif"), Some(0.));
+    }
+}
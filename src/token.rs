@@ -0,0 +1,136 @@
+use std::marker::PhantomData;
+
+use crate::{
+    AsNegotiationStr, EncodingNegotiation, Error, NegotiationKind, NegotiationType, Quality,
+};
+
+/// Name of a header holding a comma separated list of tokens with optional `q` weights. The name
+/// must be lowercase.
+pub trait TokenHeader {
+    const NAME: &'static str;
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Te;
+
+impl TokenHeader for Te {
+    const NAME: &'static str = "te";
+}
+
+// Token lists negotiated without a header, e.g. by `negotiate_dyn`, which never read its name.
+pub(crate) type AnyTokenNegotiation = TokenNegotiation<Te>;
+
+/// Negotiation of a token list header, shaped like `Accept-Encoding`, named by `H`.
+#[derive(Copy, Clone, Debug)]
+pub struct TokenNegotiation<H>(PhantomData<H>);
+
+impl<H: TokenHeader> NegotiationType for TokenNegotiation<H> {
+    type Parsed = <EncodingNegotiation as NegotiationType>::Parsed;
+    type Range<'a> = <EncodingNegotiation as NegotiationType>::Range<'a>;
+
+    const KIND: NegotiationKind = NegotiationKind::Token;
+
+    fn parse_elem<M: AsNegotiationStr>(input: &M) -> Result<Self::Parsed, Error> {
+        EncodingNegotiation::parse_elem(input)
+    }
+
+    fn parse_header_elem(input: &str) -> Result<(Self::Range<'_>, Quality), Error> {
+        EncodingNegotiation::parse_header_elem(input)
+    }
+
+    fn parse_quirky_header_elem(input: &str) -> Result<(Self::Range<'_>, Quality), Error> {
+        EncodingNegotiation::parse_quirky_header_elem(input)
+    }
+
+    // Unlike `Accept-Encoding`, an empty list accepts nothing: no token stands for `identity`.
+    fn empty_header<'a>() -> Option<(Self::Range<'a>, Quality)> {
+        None
+    }
+
+    fn precedence(range: &Self::Range<'_>) -> (u8, usize) {
+        EncodingNegotiation::precedence(range)
+    }
+
+    fn matches(supported: &Self::Parsed, range: &Self::Range<'_>) -> bool {
+        EncodingNegotiation::matches(supported, range)
+    }
+
+    fn is_wildcard(range: &Self::Range<'_>) -> bool {
+        EncodingNegotiation::is_wildcard(range)
+    }
+
+    fn format_range(range: &Self::Range<'_>) -> String {
+        EncodingNegotiation::format_range(range)
+    }
+
+    #[cfg(feature = "http")]
+    fn associated_header() -> http::header::HeaderName {
+        http::header::HeaderName::from_static(H::NAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Te, TokenHeader, TokenNegotiation};
+    use crate::{Error, NegotiationKind, Negotiator};
+
+    struct ResponseFormat;
+
+    impl TokenHeader for ResponseFormat {
+        const NAME: &'static str = "x-response-format";
+    }
+
+    #[test]
+    fn negotiate() {
+        let negotiator =
+            Negotiator::<TokenNegotiation<ResponseFormat>, _>::new(["compact", "pretty"]).unwrap();
+        assert_eq!(
+            negotiator.negotiate("pretty, compact;q=0.5").unwrap(),
+            Some(&"pretty")
+        );
+        assert_eq!(negotiator.negotiate("*;q=0.1").unwrap(), Some(&"compact"));
+        assert!(negotiator.negotiate("verbose").unwrap().is_none());
+
+        assert_eq!(
+            Negotiator::<TokenNegotiation<Te>, _>::new(["trailers;q=1"]).unwrap_err(),
+            Error::ParamsNotAllowed
+        );
+    }
+
+    #[test]
+    fn no_identity() {
+        let negotiator = Negotiator::<TokenNegotiation<Te>, _>::new(["identity"]).unwrap();
+        assert!(negotiator.negotiate("").unwrap().is_none());
+        assert!(negotiator.negotiate("trailers").unwrap().is_none());
+        assert!(
+            crate::negotiate_dyn(NegotiationKind::Token, &["identity"], "trailers")
+                .unwrap()
+                .is_none()
+        );
+
+        let negotiator = Negotiator::<TokenNegotiation<Te>, _>::builder()
+            .extend(["trailers"])
+            .browser_quirks(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            negotiator.negotiate("trailers;q = 0.5").unwrap(),
+            Some(&"trailers")
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn associated_header() {
+        use http::HeaderMap;
+
+        let negotiator =
+            Negotiator::<TokenNegotiation<ResponseFormat>, _>::new(["compact"]).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Response-Format", "compact".parse().unwrap());
+        assert_eq!(
+            negotiator.negotiate_from_headers(&headers).unwrap(),
+            Some(&"compact")
+        );
+    }
+}